@@ -0,0 +1 @@
+elephantry_benchmark::bench! {diesel_bench::Connection}
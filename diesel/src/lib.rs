@@ -1,12 +1,11 @@
-#![feature(test)]
-#![allow(soft_unstable)]
-
-extern crate test;
-
 use diesel::prelude::*;
 
+/**
+ * Inserts `None` as null, not as the default value, to be copied by
+ * `copy_from`.
+ */
 #[derive(diesel::Insertable)]
-#[diesel(table_name = users)]
+#[diesel(table_name = users, treat_none_as_default_value = false)]
 pub struct NewUser<'a> {
     name: &'a str,
     hair_color: Option<&'a str>,
@@ -21,6 +20,30 @@ impl<'a> NewUser<'a> {
     }
 }
 
+impl Default for NewUser<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(diesel::Insertable)]
+#[diesel(table_name = users)]
+pub struct UpsertUser<'a> {
+    id: uuid::Uuid,
+    name: &'a str,
+    hair_color: Option<&'a str>,
+}
+
+impl<'a> UpsertUser<'a> {
+    pub fn new(id: uuid::Uuid) -> Self {
+        UpsertUser {
+            id,
+            name: "User",
+            hair_color: Some("hair color"),
+        }
+    }
+}
+
 diesel::table! {
     users {
         id -> Uuid,
@@ -39,15 +62,43 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::MoodType;
+
+    types {
+        id -> Int4,
+        document -> Jsonb,
+        amount -> Numeric,
+        data -> Bytea,
+        integers -> Array<Int4>,
+        floats -> Array<Float8>,
+        created_at -> Timestamptz,
+        duration -> Interval,
+        address -> Inet,
+        mood -> MoodType,
+        period -> Range<Int4>,
+    }
+}
+
+diesel::table! {
+    payloads {
+        id -> Int4,
+        data -> Bytea,
+        content -> Text,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(users, posts);
 joinable!(posts -> users (author));
 
-#[derive(Clone, Queryable, Identifiable)]
+#[derive(Clone, Queryable, QueryableByName, Identifiable)]
+#[diesel(table_name = users)]
 pub struct User {
-    id: uuid::Uuid,
-    name: String,
-    hair_color: Option<String>,
-    created_at: chrono::NaiveDateTime,
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub hair_color: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
 }
 
 #[derive(diesel::Insertable)]
@@ -62,18 +113,141 @@ pub struct NewPost {
 #[diesel(table_name = posts)]
 #[diesel(belongs_to(User, foreign_key = author))]
 pub struct Post {
-    id: uuid::Uuid,
-    title: String,
-    content: String,
-    author: uuid::Uuid,
+    pub id: uuid::Uuid,
+    pub title: String,
+    pub content: String,
+    pub author: uuid::Uuid,
+}
+
+#[derive(Queryable)]
+pub struct Types {
+    pub id: i32,
+    pub document: serde_json::Value,
+    pub amount: bigdecimal::BigDecimal,
+    pub data: Vec<u8>,
+    pub integers: Vec<i32>,
+    pub floats: Vec<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub duration: diesel::pg::data_types::PgInterval,
+    pub address: ipnet::IpNet,
+    pub mood: Mood,
+    pub period: (std::ops::Bound<i32>, std::ops::Bound<i32>),
+}
+
+#[derive(diesel::Insertable)]
+#[diesel(table_name = types)]
+pub struct NewTypes {
+    document: serde_json::Value,
+    amount: bigdecimal::BigDecimal,
+    data: Vec<u8>,
+    integers: Vec<i32>,
+    floats: Vec<f64>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    duration: diesel::pg::data_types::PgInterval,
+    address: ipnet::IpNet,
+    mood: Mood,
+    period: (std::ops::Bound<i32>, std::ops::Bound<i32>),
+}
+
+impl NewTypes {
+    pub fn new() -> Self {
+        elephantry_benchmark::types::NewTypes::new().into()
+    }
+}
+
+impl From<elephantry_benchmark::types::NewTypes> for NewTypes {
+    fn from(types: elephantry_benchmark::types::NewTypes) -> Self {
+        Self {
+            document: types.document,
+            amount: bigdecimal::BigDecimal::new(types.amount.0.into(), types.amount.1.into()),
+            data: types.data,
+            integers: types.integers,
+            floats: types.floats,
+            created_at: chrono::DateTime::from_timestamp(types.created_at, 0).unwrap(),
+            duration: diesel::pg::data_types::PgInterval::new(
+                types.duration.microseconds,
+                types.duration.days,
+                types.duration.months,
+            ),
+            address: types.address.into(),
+            mood: types.mood.into(),
+            period: types.period,
+        }
+    }
+}
+
+impl Default for NewTypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(diesel::SqlType, diesel::QueryId)]
+#[diesel(postgres_type(name = "mood"))]
+pub struct MoodType;
+
+#[derive(Clone, Copy, Debug, diesel::AsExpression, diesel::FromSqlRow)]
+#[diesel(sql_type = MoodType)]
+pub enum Mood {
+    Sad,
+    Ok,
+    Happy,
+}
+
+impl From<elephantry_benchmark::types::Mood> for Mood {
+    fn from(mood: elephantry_benchmark::types::Mood) -> Self {
+        use elephantry_benchmark::types;
+
+        match mood {
+            types::Mood::Sad => Self::Sad,
+            types::Mood::Ok => Self::Ok,
+            types::Mood::Happy => Self::Happy,
+        }
+    }
+}
+
+impl diesel::serialize::ToSql<MoodType, diesel::pg::Pg> for Mood {
+    fn to_sql<'b>(
+        &'b self,
+        out: &mut diesel::serialize::Output<'b, '_, diesel::pg::Pg>,
+    ) -> diesel::serialize::Result {
+        use std::io::Write;
+
+        let label: &[u8] = match self {
+            Self::Sad => b"Sad",
+            Self::Ok => b"Ok",
+            Self::Happy => b"Happy",
+        };
+        out.write_all(label)?;
+
+        Ok(diesel::serialize::IsNull::No)
+    }
+}
+
+impl diesel::deserialize::FromSql<MoodType, diesel::pg::Pg> for Mood {
+    fn from_sql(value: diesel::pg::PgValue<'_>) -> diesel::deserialize::Result<Self> {
+        match value.as_bytes() {
+            b"Sad" => Ok(Self::Sad),
+            b"Ok" => Ok(Self::Ok),
+            b"Happy" => Ok(Self::Happy),
+            _ => Err("unknown mood".into()),
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct Name {
+    #[diesel(sql_type = diesel::sql_types::VarChar)]
+    name: String,
 }
 
-struct Connection(diesel::pg::PgConnection);
+pub struct Connection(diesel::pg::PgConnection);
 
 impl elephantry_benchmark::Client for Connection {
     type Error = diesel::result::Error;
     type User = User;
     type Post = Post;
+    type Types = Types;
 
     fn create(dsn: &str) -> Result<Self, Self::Error> {
         use diesel::Connection;
@@ -95,13 +269,30 @@ impl elephantry_benchmark::Client for Connection {
             .map(|_| ())
     }
 
-    fn insert_users(&mut self, n: usize) -> Result<(), Self::Error> {
-        let users = (0..n).map(|_| NewUser::new()).collect::<Vec<_>>();
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
 
-        diesel::insert_into(users::table)
-            .values(&users)
-            .execute(&mut self.0)
-            .map(|_| ())
+        const {
+            &[
+                Implementation::idiomatic("batch"),
+                Implementation::optimized("copy"),
+            ]
+        }
+    }
+
+    fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "batch" => {
+                let users = (0..n).map(|_| NewUser::new()).collect::<Vec<_>>();
+
+                diesel::insert_into(users::table)
+                    .values(&users)
+                    .execute(&mut self.0)
+                    .map(|_| ())
+            }
+            "copy" => self.copy_in(elephantry_benchmark::CopyFormat::Binary, n),
+            _ => unreachable!(),
+        }
     }
 
     fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
@@ -114,10 +305,10 @@ impl elephantry_benchmark::Client for Connection {
         Ok(results.first().unwrap().clone())
     }
 
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error> {
+    fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
         let results = users::table.load::<User>(&mut self.0)?;
 
-        Ok(results[9_999].clone())
+        Ok(results[rows - 1].clone())
     }
 
     fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error> {
@@ -141,6 +332,322 @@ impl elephantry_benchmark::Client for Connection {
 
         Ok(users_and_posts)
     }
+
+    fn update_user(&mut self) -> Result<(), Self::Error> {
+        diesel::update(users::table.find(elephantry_benchmark::UUID))
+            .set((users::name.eq("User"), users::hair_color.eq("hair color")))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn update_users(&mut self) -> Result<(), Self::Error> {
+        diesel::update(users::table)
+            .set(users::hair_color.eq("hair color"))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        diesel::delete(users::table.find(id))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        use diesel::upsert::excluded;
+
+        diesel::insert_into(users::table)
+            .values(&UpsertUser::new(id))
+            .on_conflict(users::id)
+            .do_update()
+            .set((
+                users::name.eq(excluded(users::name)),
+                users::hair_color.eq(excluded(users::hair_color)),
+            ))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        use diesel::upsert::excluded;
+
+        let users = ids.iter().map(|x| UpsertUser::new(*x)).collect::<Vec<_>>();
+
+        diesel::insert_into(users::table)
+            .values(&users)
+            .on_conflict(users::id)
+            .do_update()
+            .set((
+                users::name.eq(excluded(users::name)),
+                users::hair_color.eq(excluded(users::hair_color)),
+            ))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn transaction(&mut self) -> Result<(), Self::Error> {
+        use diesel::Connection;
+
+        self.0.transaction(|conn| {
+            diesel::insert_into(users::table)
+                .values(&NewUser::new())
+                .execute(conn)?;
+            diesel::update(users::table.find(elephantry_benchmark::UUID))
+                .set(users::hair_color.eq("hair color"))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    fn rollback(&mut self) -> Result<(), Self::Error> {
+        use diesel::Connection;
+
+        let result = self.0.transaction(|conn| {
+            diesel::insert_into(users::table)
+                .values(&NewUser::new())
+                .execute(conn)?;
+
+            Err(diesel::result::Error::RollbackTransaction)
+        });
+
+        rolled_back(result)
+    }
+
+    fn savepoints(&mut self) -> Result<(), Self::Error> {
+        use diesel::Connection;
+
+        self.0.transaction(|conn| {
+            diesel::insert_into(users::table)
+                .values(&NewUser::new())
+                .execute(conn)?;
+
+            conn.transaction(|conn| {
+                diesel::insert_into(users::table)
+                    .values(&NewUser::new())
+                    .execute(conn)?;
+
+                let result = conn.transaction(|conn| {
+                    diesel::insert_into(users::table)
+                        .values(&NewUser::new())
+                        .execute(conn)?;
+
+                    Err(diesel::result::Error::RollbackTransaction)
+                });
+
+                rolled_back(result)
+            })
+        })
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use diesel::Connection;
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+
+        let offset = (page - 1) * PAGE_SIZE;
+
+        match pagination {
+            Pagination::Offset => users::table
+                .order(users::id)
+                .limit(PAGE_SIZE as i64)
+                .offset(offset as i64)
+                .load(&mut self.0),
+            Pagination::Keyset => users::table
+                .filter(users::id.gt(elephantry_benchmark::page_key(page)))
+                .order(users::id)
+                .limit(PAGE_SIZE as i64)
+                .load(&mut self.0),
+            // No cursor in the DSL.
+            Pagination::Cursor => self.0.transaction(|conn| {
+                use diesel::connection::SimpleConnection;
+
+                conn.batch_execute(&format!(
+                    "declare users_page cursor for select id, name, hair_color, created_at from users order by id;
+                    move absolute {offset} in users_page"
+                ))?;
+
+                diesel::sql_query(format!("fetch {PAGE_SIZE} from users_page")).load(conn)
+            }),
+        }
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &[
+            elephantry_benchmark::Mode::Unprepared,
+            elephantry_benchmark::Mode::Prepared,
+        ]
+    }
+
+    fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+
+        match mode {
+            // Raw SQL queries aren't kept in the statement cache.
+            Mode::Unprepared => diesel::sql_query("SELECT name FROM users WHERE id = $1")
+                .bind::<diesel::sql_types::Uuid, _>(elephantry_benchmark::UUID)
+                .get_result::<Name>(&mut self.0)
+                .map(|x| x.name),
+            Mode::Prepared => users::table
+                .find(elephantry_benchmark::UUID)
+                .select(users::name)
+                .first(&mut self.0),
+            Mode::Simple => unreachable!(),
+        }
+    }
+
+    fn stream(&mut self) -> Result<usize, Self::Error> {
+        users::table
+            .select(users::name)
+            .load_iter::<String, diesel::pg::PgRowByRowLoadingMode>(&mut self.0)?
+            .try_fold(0, |len, name| Ok(len + name?.len()))
+    }
+
+    fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        types::table.load(&mut self.0)
+    }
+
+    fn insert_types(&mut self) -> Result<(), Self::Error> {
+        diesel::insert_into(types::table)
+            .values(&NewTypes::new())
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        diesel::insert_into(payloads::table)
+            .values(payloads::data.eq(data))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let data: Vec<u8> = payloads::table
+            .find(1)
+            .select(payloads::data)
+            .first(&mut self.0)?;
+
+        Ok(data.len())
+    }
+
+    fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        diesel::insert_into(payloads::table)
+            .values(payloads::content.eq(content))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let content: String = payloads::table
+            .find(1)
+            .select(payloads::content)
+            .first(&mut self.0)?;
+
+        Ok(content.len())
+    }
+
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        if format == elephantry_benchmark::CopyFormat::Binary {
+            let users = (0..n).map(|_| NewUser::new()).collect::<Vec<_>>();
+
+            return diesel::copy_from(users::table)
+                .from_insertable(&users)
+                .execute(&mut self.0)
+                .map(|_| ());
+        }
+
+        let data = format.users(n);
+
+        diesel::copy_from(users::table)
+            .from_raw_data((users::name, users::hair_color), |copy| {
+                copy.write_all(&data)
+                    .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))
+            })
+            .with_format(copy_format(format))
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
+
+    /**
+     * `copy_to` doesn't read the result closing the copy, the connection
+     * then fails to send any other query ("another command is already in
+     * progress").
+     */
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &[]
+    }
+
+    fn copy_out(&mut self, _: elephantry_benchmark::CopyFormat) -> Result<usize, Self::Error> {
+        unreachable!()
+    }
+
+    fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        use elephantry_benchmark::dataset;
+
+        let data = dataset::users_text(users);
+
+        diesel::copy_from(users::table)
+            .from_raw_data(
+                (users::id, users::name, users::hair_color, users::created_at),
+                |copy| {
+                    copy.write_all(&data)
+                        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))
+                },
+            )
+            .with_format(diesel::pg::CopyFormat::Text)
+            .execute(&mut self.0)?;
+
+        let data = dataset::posts_text(posts);
+
+        diesel::copy_from(posts::table)
+            .from_raw_data(
+                (posts::id, posts::title, posts::content, posts::author),
+                |copy| {
+                    copy.write_all(&data)
+                        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))
+                },
+            )
+            .with_format(diesel::pg::CopyFormat::Text)
+            .execute(&mut self.0)
+            .map(|_| ())
+    }
 }
 
-elephantry_benchmark::bench! {Connection}
+fn copy_format(format: elephantry_benchmark::CopyFormat) -> diesel::pg::CopyFormat {
+    use elephantry_benchmark::CopyFormat;
+
+    match format {
+        CopyFormat::Text => diesel::pg::CopyFormat::Text,
+        CopyFormat::Csv => diesel::pg::CopyFormat::Csv,
+        CopyFormat::Binary => diesel::pg::CopyFormat::Binary,
+    }
+}
+
+/**
+ * A transaction rolled back on purpose isn't an error.
+ */
+fn rolled_back(result: QueryResult<()>) -> QueryResult<()> {
+    match result {
+        Err(diesel::result::Error::RollbackTransaction) => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1 @@
+elephantry_benchmark::bench! {elephantry_bench::Connection}
@@ -1,11 +1,6 @@
-#![feature(test)]
-#![allow(soft_unstable)]
-
-extern crate test;
-
 mod user {
     #[derive(Clone, elephantry::Entity)]
-    #[elephantry(model = "Model", structure = "Structure", relation = "public.users")]
+    #[elephantry(model = "Model", structure = "Structure", relation = "users")]
     pub struct Entity {
         #[elephantry(pk)]
         pub id: Option<uuid::Uuid>,
@@ -20,8 +15,8 @@ mod user {
         pub fn new() -> Self {
             Self {
                 id: None,
-                name: format!("User"),
-                hair_color: Some(format!("hair color")),
+                name: "User".to_string(),
+                hair_color: Some("hair color".to_string()),
                 created_at: None,
                 posts: Vec::new(),
             }
@@ -30,8 +25,8 @@ mod user {
         pub fn with_id(id: uuid::Uuid) -> Self {
             Self {
                 id: Some(id),
-                name: format!("User"),
-                hair_color: Some(format!("hair color")),
+                name: "User".to_string(),
+                hair_color: Some("hair color".to_string()),
                 created_at: Some(chrono::offset::Local::now().naive_local()),
                 posts: Vec::new(),
             }
@@ -82,7 +77,7 @@ select {projection}
 
 mod post {
     #[derive(Clone, elephantry::Entity, elephantry::Composite)]
-    #[elephantry(model = "Model", structure = "Structure", relation = "public.posts")]
+    #[elephantry(model = "Model", structure = "Structure", relation = "posts")]
     pub struct Entity {
         #[elephantry(pk)]
         pub id: Option<uuid::Uuid>,
@@ -91,19 +86,118 @@ mod post {
     }
 }
 
-struct Connection(elephantry::Pool);
+/**
+ * Row of the posts table with its author, to copy the generated posts.
+ */
+mod post_row {
+    #[derive(elephantry::Entity)]
+    #[elephantry(model = "Model", structure = "Structure", relation = "posts")]
+    pub struct Entity {
+        #[elephantry(pk)]
+        pub id: uuid::Uuid,
+        pub title: String,
+        pub content: String,
+        pub author: uuid::Uuid,
+    }
+}
+
+mod types {
+    #[derive(elephantry::Entity)]
+    #[elephantry(model = "Model", structure = "Structure", relation = "types")]
+    pub struct Entity {
+        #[elephantry(pk)]
+        pub id: Option<i32>,
+        pub document: elephantry::Jsonb,
+        pub amount: bigdecimal::BigDecimal,
+        pub data: elephantry::Bytea,
+        pub integers: Vec<i32>,
+        pub floats: Vec<f64>,
+        pub created_at: chrono::DateTime<chrono::Utc>,
+        pub duration: elephantry::Interval,
+        pub address: std::net::IpAddr,
+        pub mood: Mood,
+        pub period: std::ops::Range<i32>,
+    }
+
+    impl Entity {
+        pub fn new() -> Self {
+            elephantry_benchmark::types::NewTypes::new().into()
+        }
+    }
+
+    impl From<elephantry_benchmark::types::NewTypes> for Entity {
+        fn from(types: elephantry_benchmark::types::NewTypes) -> Self {
+            use std::ops::Bound;
+
+            let period = match types.period {
+                (Bound::Included(start), Bound::Excluded(end)) => start..end,
+                period => unreachable!("not a half-open range: {period:?}"),
+            };
+
+            Self {
+                id: None,
+                document: types.document.into(),
+                amount: bigdecimal::BigDecimal::new(types.amount.0.into(), types.amount.1.into()),
+                // Bytea parameters are sent as C strings, the bytes stop at
+                // the first null one.
+                data: types.data.into(),
+                integers: types.integers,
+                floats: types.floats,
+                created_at: chrono::DateTime::from_timestamp(types.created_at, 0).unwrap(),
+                duration: elephantry::Interval::new(
+                    0,
+                    types.duration.months,
+                    types.duration.days,
+                    0,
+                    0,
+                    (types.duration.microseconds / 1_000_000) as i32,
+                    (types.duration.microseconds % 1_000_000) as i32,
+                ),
+                address: types.address,
+                mood: types.mood.into(),
+                period,
+            }
+        }
+    }
+
+    #[derive(Debug, elephantry::Enum)]
+    pub enum Mood {
+        Sad,
+        Ok,
+        Happy,
+    }
+
+    impl From<elephantry_benchmark::types::Mood> for Mood {
+        fn from(mood: elephantry_benchmark::types::Mood) -> Self {
+            use elephantry_benchmark::types;
+
+            match mood {
+                types::Mood::Sad => Self::Sad,
+                types::Mood::Ok => Self::Ok,
+                types::Mood::Happy => Self::Happy,
+            }
+        }
+    }
+}
+
+pub struct Connection(elephantry::Pool);
 
 impl elephantry_benchmark::Client for Connection {
     type Error = elephantry::Error;
     type User = user::Entity;
     type Post = post::Entity;
+    type Types = types::Entity;
 
     fn create(dsn: &str) -> Result<Self, Self::Error> {
         elephantry::Pool::new(dsn).map(Self)
     }
 
+    fn worker(&self, _: &str) -> Result<Self, Self::Error> {
+        Ok(Self(self.0.clone()))
+    }
+
     fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
-        self.0.execute(&query).map(|_| ())
+        self.0.execute(query).map(|_| ())
     }
 
     fn insert_user(&mut self) -> Result<(), Self::Error> {
@@ -112,10 +206,18 @@ impl elephantry_benchmark::Client for Connection {
             .map(|_| ())
     }
 
-    fn insert_users(&mut self, n: usize) -> Result<(), Self::Error> {
-        let users = (0..n).map(|_| user::Entity::with_id(uuid::Uuid::new_v4()));
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
 
-        self.0.copy::<user::Model, _>(users)
+        const { &[Implementation::LOOP, Implementation::optimized("copy")] }
+    }
+
+    fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "loop" => self.insert_users(n),
+            "copy" => self.copy_in(elephantry_benchmark::CopyFormat::Binary, n),
+            _ => unreachable!(),
+        }
     }
 
     fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
@@ -133,8 +235,8 @@ impl elephantry_benchmark::Client for Connection {
         Ok(result.unwrap())
     }
 
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error> {
-        let result = self.0.find_all::<user::Model>(None)?.get(9_999);
+    fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
+        let result = self.0.find_all::<user::Model>(None)?.get(rows - 1);
 
         Ok(result)
     }
@@ -160,6 +262,286 @@ impl elephantry_benchmark::Client for Connection {
             })
             .collect())
     }
-}
 
-elephantry_benchmark::bench! {Connection}
+    fn update_user(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .update_by_pk::<user::Model>(
+                &elephantry::pk!(id => elephantry_benchmark::UUID),
+                &elephantry::values!(name => "User", hair_color => "hair color"),
+            )
+            .map(|_| ())
+    }
+
+    fn update_users(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .query::<()>("update users set hair_color = $1", &[&"hair color"])
+            .map(|_| ())
+    }
+
+    fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .delete_by_pk::<user::Model>(&elephantry::pk!(id))
+            .map(|_| ())
+    }
+
+    fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .upsert_one::<user::Model>(
+                &user::Entity::with_id(id),
+                "(id)",
+                "update set name = excluded.name, hair_color = excluded.hair_color",
+            )
+            .map(|_| ())
+    }
+
+    /**
+     * elephantry only upserts one entity at a time, the batch is a query.
+     */
+    fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        self.0
+            .query::<uuid::Uuid>(
+                "insert into users (id, name, hair_color) select id, $2, $3 from unnest($1::uuid[]) as id
+                    on conflict (id) do update set name = excluded.name, hair_color = excluded.hair_color",
+                &[&ids.to_vec(), &"User", &"hair color"],
+            )
+            .map(|_| ())
+    }
+
+    fn transaction(&mut self) -> Result<(), Self::Error> {
+        let transaction = self.0.transaction();
+
+        transaction.start()?;
+        self.0.insert_one::<user::Model>(&user::Entity::new())?;
+        self.0.update_by_pk::<user::Model>(
+            &elephantry::pk!(id => elephantry_benchmark::UUID),
+            &elephantry::values!(hair_color => "hair color"),
+        )?;
+        transaction.commit()
+    }
+
+    fn rollback(&mut self) -> Result<(), Self::Error> {
+        let transaction = self.0.transaction();
+
+        transaction.start()?;
+        self.0.insert_one::<user::Model>(&user::Entity::new())?;
+        transaction.roolback(None)
+    }
+
+    fn savepoints(&mut self) -> Result<(), Self::Error> {
+        let transaction = self.0.transaction();
+
+        transaction.start()?;
+        self.0.insert_one::<user::Model>(&user::Entity::new())?;
+        transaction.set_save_point("savepoint_1")?;
+        self.0.insert_one::<user::Model>(&user::Entity::new())?;
+        transaction.set_save_point("savepoint_2")?;
+        self.0.insert_one::<user::Model>(&user::Entity::new())?;
+        transaction.roolback(Some("savepoint_2"))?;
+        transaction.release_savepoint("savepoint_1")?;
+        transaction.commit()
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+
+        match pagination {
+            // The pager also counts the users, for the number of pages.
+            Pagination::Offset => {
+                let pager = self.0.paginate_find_where::<user::Model>(
+                    "true",
+                    &[],
+                    PAGE_SIZE,
+                    page,
+                    Some("order by id"),
+                )?;
+
+                Ok(pager.into_iter().collect())
+            }
+            Pagination::Keyset => {
+                let users = self.0.find_where::<user::Model>(
+                    "id > $1",
+                    &[&elephantry_benchmark::page_key(page)],
+                    Some(&format!("order by id fetch first {PAGE_SIZE} rows only")),
+                )?;
+
+                Ok(users.collect())
+            }
+            Pagination::Cursor => {
+                let transaction = self.0.transaction();
+
+                transaction.start()?;
+
+                let users = self
+                    .0
+                    .execute(&format!(
+                        "declare users_page cursor for select id, name, hair_color, created_at from users order by id;
+                        move absolute {} in users_page",
+                        (page - 1) * PAGE_SIZE
+                    ))
+                    .and_then(|_| {
+                        self.0
+                            .query::<user::Entity>(&format!("fetch {PAGE_SIZE} from users_page"), &[])
+                    });
+
+                match users {
+                    Ok(users) => {
+                        let users = users.collect();
+                        transaction.commit()?;
+
+                        Ok(users)
+                    }
+                    Err(err) => {
+                        // Closes the cursor and ends the aborted transaction,
+                        // the error worth reporting is the one of the page.
+                        transaction.roolback(None).ok();
+
+                        Err(err)
+                    }
+                }
+            }
+        }
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &[
+            elephantry_benchmark::Mode::Simple,
+            elephantry_benchmark::Mode::Unprepared,
+        ]
+    }
+
+    fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+
+        match mode {
+            Mode::Simple => {
+                let query = format!(
+                    "select name from users where id = '{}'",
+                    elephantry_benchmark::UUID
+                );
+
+                Ok(self.0.execute(&query)?.get(0).get("name"))
+            }
+            Mode::Unprepared => self.0.query_one::<String>(
+                "select name from users where id = $1",
+                &[&elephantry_benchmark::UUID],
+            ),
+            Mode::Prepared => unreachable!(),
+        }
+    }
+
+    fn stream(&mut self) -> Result<usize, Self::Error> {
+        // Rows are decoded lazily, but libpq receives the whole result set.
+        let len = self
+            .0
+            .query::<String>("select name from users", &[])?
+            .map(|name| name.len())
+            .sum();
+
+        Ok(len)
+    }
+
+    fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        let results = self.0.find_all::<types::Model>(None)?.collect();
+
+        Ok(results)
+    }
+
+    fn insert_types(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .insert_one::<types::Model>(&types::Entity::new())
+            .map(|_| ())
+    }
+
+    fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0
+            .query::<()>(
+                "insert into payloads (data) values ($1)",
+                &[&elephantry::Bytea::from(data.to_vec())],
+            )
+            .map(|_| ())
+    }
+
+    fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let data = self
+            .0
+            .query_one::<elephantry::Bytea>("select data from payloads where id = 1", &[])?;
+
+        Ok(data.len())
+    }
+
+    fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.0
+            .query::<()>("insert into payloads (content) values ($1)", &[&content])
+            .map(|_| ())
+    }
+
+    fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let content = self
+            .0
+            .query_one::<String>("select content from payloads where id = 1", &[])?;
+
+        Ok(content.len())
+    }
+
+    /**
+     * Entities are only copied in the binary format, the connection has no
+     * copy out.
+     */
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &[elephantry_benchmark::CopyFormat::Binary]
+    }
+
+    fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        debug_assert_eq!(format, elephantry_benchmark::CopyFormat::Binary);
+
+        let users = (0..n).map(|_| user::Entity::with_id(uuid::Uuid::new_v4()));
+
+        self.0.copy::<user::Model, _>(users)
+    }
+
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &[]
+    }
+
+    fn copy_out(&mut self, _: elephantry_benchmark::CopyFormat) -> Result<usize, Self::Error> {
+        unreachable!()
+    }
+
+    fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        let users = users.iter().map(|x| user::Entity {
+            id: Some(x.id),
+            name: x.name.clone(),
+            hair_color: x.hair_color.clone(),
+            created_at: chrono::DateTime::from_timestamp_micros(x.created_at)
+                .map(|x| x.naive_utc()),
+            posts: Vec::new(),
+        });
+
+        self.0.copy::<user::Model, _>(users)?;
+
+        let posts = posts.iter().map(|x| post_row::Entity {
+            id: x.id,
+            title: x.title.clone(),
+            content: x.content.clone(),
+            author: x.author,
+        });
+
+        self.0.copy::<post_row::Model, _>(posts)
+    }
+}
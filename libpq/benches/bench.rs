@@ -0,0 +1 @@
+elephantry_benchmark::bench! {libpq_bench::Connection}
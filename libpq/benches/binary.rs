@@ -0,0 +1 @@
+elephantry_benchmark::bench! {libpq_bench::Connection<libpq_bench::Binary>, "libpq-binary"}
@@ -0,0 +1,599 @@
+use crate::{Interval, Range};
+use std::ops::Bound;
+
+/**
+ * Format of the results requested to the server, and how their values are
+ * decoded.
+ */
+pub trait Format {
+    const FORMAT: libpq::Format;
+
+    fn uuid(value: &[u8]) -> uuid::Uuid;
+
+    fn string(value: &[u8]) -> String {
+        String::from_utf8(value.to_vec()).unwrap()
+    }
+
+    fn i32(value: &[u8]) -> i32;
+
+    fn f64(value: &[u8]) -> f64;
+
+    fn timestamp(value: &[u8]) -> chrono::NaiveDateTime;
+
+    fn timestamptz(value: &[u8]) -> chrono::DateTime<chrono::Utc>;
+
+    fn array<T>(value: &[u8], element: fn(&[u8]) -> T) -> Vec<T>;
+
+    fn json(value: &[u8]) -> serde_json::Value;
+
+    fn numeric(value: &[u8]) -> rust_decimal::Decimal;
+
+    fn bytea(value: &[u8]) -> Vec<u8>;
+
+    fn interval(value: &[u8]) -> Interval;
+
+    fn inet(value: &[u8]) -> std::net::IpAddr;
+
+    fn range(value: &[u8]) -> Range;
+}
+
+/**
+ * Values as strings, parsed client side.
+ */
+pub struct Text;
+
+impl Text {
+    fn str(value: &[u8]) -> &str {
+        std::str::from_utf8(value).unwrap()
+    }
+}
+
+impl Format for Text {
+    const FORMAT: libpq::Format = libpq::Format::Text;
+
+    fn uuid(value: &[u8]) -> uuid::Uuid {
+        Self::str(value).parse().unwrap()
+    }
+
+    fn i32(value: &[u8]) -> i32 {
+        Self::str(value).parse().unwrap()
+    }
+
+    fn f64(value: &[u8]) -> f64 {
+        Self::str(value).parse().unwrap()
+    }
+
+    fn timestamp(value: &[u8]) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(Self::str(value), "%F %T%.f").unwrap()
+    }
+
+    fn timestamptz(value: &[u8]) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_str(Self::str(value), "%F %T%.f%#z")
+            .unwrap()
+            .to_utc()
+    }
+
+    /**
+     * One dimension array of elements without comma.
+     */
+    fn array<T>(value: &[u8], element: fn(&[u8]) -> T) -> Vec<T> {
+        let value = Self::str(value).trim_matches(|c| c == '{' || c == '}');
+
+        if value.is_empty() {
+            return Vec::new();
+        }
+
+        value
+            .split(",")
+            .map(|x| element(x.trim_matches('"').as_bytes()))
+            .collect()
+    }
+
+    fn json(value: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(value).unwrap()
+    }
+
+    fn numeric(value: &[u8]) -> rust_decimal::Decimal {
+        Self::str(value).parse().unwrap()
+    }
+
+    /**
+     * Hex format, `\x` followed by two digits per byte.
+     */
+    fn bytea(value: &[u8]) -> Vec<u8> {
+        value[2..]
+            .chunks(2)
+            .map(|x| u8::from_str_radix(Self::str(x), 16).unwrap())
+            .collect()
+    }
+
+    /**
+     * `postgres` interval style: `1 year 2 mons 3 days 04:05:06.7`.
+     */
+    fn interval(value: &[u8]) -> Interval {
+        let mut interval = Interval::default();
+        let mut tokens = Self::str(value).split_whitespace();
+
+        while let Some(token) = tokens.next() {
+            if token.contains(':') {
+                let negative = token.starts_with('-');
+                let mut parts = token.trim_start_matches(['-', '+']).split(':');
+                let hours = parts.next().unwrap().parse::<i64>().unwrap();
+                let minutes = parts.next().unwrap().parse::<i64>().unwrap();
+                let seconds = parts.next().unwrap().parse::<f64>().unwrap();
+                let microseconds = (hours * 3_600 + minutes * 60) * 1_000_000
+                    + (seconds * 1_000_000.).round() as i64;
+
+                interval.microseconds = if negative {
+                    -microseconds
+                } else {
+                    microseconds
+                };
+            } else {
+                let n = token.parse::<i32>().unwrap();
+
+                match tokens.next().unwrap().trim_end_matches('s') {
+                    "year" => interval.months += n * 12,
+                    "mon" => interval.months += n,
+                    "day" => interval.days += n,
+                    unit => panic!("unknown interval unit: {unit}"),
+                }
+            }
+        }
+
+        interval
+    }
+
+    fn inet(value: &[u8]) -> std::net::IpAddr {
+        Self::str(value).split('/').next().unwrap().parse().unwrap()
+    }
+
+    /**
+     * Canonical form of a discrete range: `[1,11)`, `(,5)` or `empty`.
+     */
+    fn range(value: &[u8]) -> Range {
+        let value = Self::str(value);
+
+        if value == "empty" {
+            return (Bound::Excluded(0), Bound::Excluded(0));
+        }
+
+        let (lower, upper) = value[1..value.len() - 1].split_once(',').unwrap();
+
+        let lower = match (lower, value.starts_with('[')) {
+            ("", _) => Bound::Unbounded,
+            (x, true) => Bound::Included(x.parse().unwrap()),
+            (x, false) => Bound::Excluded(x.parse().unwrap()),
+        };
+        let upper = match (upper, value.ends_with(']')) {
+            ("", _) => Bound::Unbounded,
+            (x, true) => Bound::Included(x.parse().unwrap()),
+            (x, false) => Bound::Excluded(x.parse().unwrap()),
+        };
+
+        (lower, upper)
+    }
+}
+
+/**
+ * Values in their binary wire representation.
+ */
+pub struct Binary;
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LOWER_INCLUSIVE: u8 = 0x02;
+const RANGE_UPPER_INCLUSIVE: u8 = 0x04;
+const RANGE_LOWER_INFINITE: u8 = 0x08;
+const RANGE_UPPER_INFINITE: u8 = 0x10;
+
+const NUMERIC_NEGATIVE: u16 = 0x4000;
+/**
+ * Sign bits of NaN and, since PostgreSQL 14, of the infinities.
+ */
+const NUMERIC_SPECIAL: u16 = 0xC000;
+
+impl Binary {
+    fn i16(value: &[u8]) -> i16 {
+        i16::from_be_bytes(value[..2].try_into().unwrap())
+    }
+
+    fn i64(value: &[u8]) -> i64 {
+        i64::from_be_bytes(value[..8].try_into().unwrap())
+    }
+}
+
+impl Format for Binary {
+    const FORMAT: libpq::Format = libpq::Format::Binary;
+
+    fn uuid(value: &[u8]) -> uuid::Uuid {
+        uuid::Uuid::from_slice(value).unwrap()
+    }
+
+    fn i32(value: &[u8]) -> i32 {
+        i32::from_be_bytes(value[..4].try_into().unwrap())
+    }
+
+    fn f64(value: &[u8]) -> f64 {
+        f64::from_be_bytes(value.try_into().unwrap())
+    }
+
+    /**
+     * Microseconds since 2000-01-01 00:00:00.
+     */
+    fn timestamp(value: &[u8]) -> chrono::NaiveDateTime {
+        let epoch = chrono::NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        epoch + chrono::TimeDelta::microseconds(Self::i64(value))
+    }
+
+    /**
+     * Same as a timestamp, in UTC.
+     */
+    fn timestamptz(value: &[u8]) -> chrono::DateTime<chrono::Utc> {
+        Self::timestamp(value).and_utc()
+    }
+
+    /**
+     * One dimension array: the number of dimensions, a null flag and the
+     * element type, the size and lower bound of the dimension, then each
+     * element prefixed by its length (-1 for null, skipped).
+     */
+    fn array<T>(value: &[u8], element: fn(&[u8]) -> T) -> Vec<T> {
+        if Self::i32(value) == 0 {
+            return Vec::new();
+        }
+
+        let len = Self::i32(&value[12..]) as usize;
+        let mut elements = Vec::with_capacity(len);
+        let mut offset = 20;
+
+        for _ in 0..len {
+            let size = Self::i32(&value[offset..]);
+            offset += 4;
+
+            if size < 0 {
+                continue;
+            }
+
+            let size = size as usize;
+            elements.push(element(&value[offset..offset + size]));
+            offset += size;
+        }
+
+        elements
+    }
+
+    /**
+     * A version number, then the JSON text.
+     */
+    fn json(value: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(&value[1..]).unwrap()
+    }
+
+    /**
+     * The number of digits, the weight of the first one, the sign and the
+     * display scale, then the digits in base 10000. A `Decimal` has no NaN
+     * nor infinity.
+     */
+    fn numeric(value: &[u8]) -> rust_decimal::Decimal {
+        let digits = Self::i16(value) as i32;
+        let weight = Self::i16(&value[2..]) as i32;
+        let sign = Self::i16(&value[4..]) as u16;
+        assert!(
+            sign & NUMERIC_SPECIAL != NUMERIC_SPECIAL,
+            "NaN or infinite numeric"
+        );
+        let negative = sign == NUMERIC_NEGATIVE;
+        let scale = Self::i16(&value[6..]) as u32;
+
+        let mut mantissa = (0..digits as usize).fold(0_i128, |mantissa, x| {
+            mantissa * 10_000 + Self::i16(&value[8 + x * 2..]) as i128
+        });
+
+        // Digits after the decimal point, in base 10.
+        let mut exponent = (digits - weight - 1) * 4;
+        while exponent < 0 {
+            mantissa *= 10;
+            exponent += 1;
+        }
+
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        let mut decimal = rust_decimal::Decimal::from_i128_with_scale(mantissa, exponent as u32);
+        decimal.rescale(scale);
+
+        decimal
+    }
+
+    fn bytea(value: &[u8]) -> Vec<u8> {
+        value.to_vec()
+    }
+
+    /**
+     * Microseconds, days, then months.
+     */
+    fn interval(value: &[u8]) -> Interval {
+        Interval {
+            microseconds: Self::i64(value),
+            days: Self::i32(&value[8..]),
+            months: Self::i32(&value[12..]),
+        }
+    }
+
+    /**
+     * The address family, the netmask bits, the cidr flag and the address
+     * length, then the address.
+     */
+    fn inet(value: &[u8]) -> std::net::IpAddr {
+        let address = &value[4..4 + value[3] as usize];
+
+        match address.len() {
+            4 => <[u8; 4]>::try_from(address).unwrap().into(),
+            _ => <[u8; 16]>::try_from(address).unwrap().into(),
+        }
+    }
+
+    /**
+     * Flags, then each finite bound prefixed by its length.
+     */
+    fn range(value: &[u8]) -> Range {
+        let flags = value[0];
+
+        if flags & RANGE_EMPTY != 0 {
+            return (Bound::Excluded(0), Bound::Excluded(0));
+        }
+
+        let mut offset = 1;
+        let mut bound = |infinite, inclusive| {
+            if flags & infinite != 0 {
+                return Bound::Unbounded;
+            }
+
+            let value = Self::i32(&value[offset + 4..]);
+            offset += 8;
+
+            if flags & inclusive != 0 {
+                Bound::Included(value)
+            } else {
+                Bound::Excluded(value)
+            }
+        };
+
+        let lower = bound(RANGE_LOWER_INFINITE, RANGE_LOWER_INCLUSIVE);
+        let upper = bound(RANGE_UPPER_INFINITE, RANGE_UPPER_INCLUSIVE);
+
+        (lower, upper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Binary, Format};
+    use std::ops::Bound;
+
+    /**
+     * Binary numeric: the digits in base 10000, their weight, the sign and
+     * the display scale.
+     */
+    fn numeric_value(digits: &[i16], weight: i16, sign: u16, scale: i16) -> Vec<u8> {
+        let mut value = Vec::new();
+
+        value.extend_from_slice(&(digits.len() as i16).to_be_bytes());
+        value.extend_from_slice(&weight.to_be_bytes());
+        value.extend_from_slice(&sign.to_be_bytes());
+        value.extend_from_slice(&scale.to_be_bytes());
+
+        for digit in digits {
+            value.extend_from_slice(&digit.to_be_bytes());
+        }
+
+        value
+    }
+
+    fn decimal(s: &str) -> rust_decimal::Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn numeric_fraction() {
+        let value = Binary::numeric(&numeric_value(&[1, 2500], 0, 0, 2));
+
+        assert_eq!(value, decimal("1.25"));
+        assert_eq!(value.scale(), 2);
+
+        // 0.0001, the first digit is after the decimal point.
+        assert_eq!(
+            Binary::numeric(&numeric_value(&[1], -1, 0, 4)),
+            decimal("0.0001")
+        );
+    }
+
+    #[test]
+    fn numeric_negative() {
+        assert_eq!(
+            Binary::numeric(&numeric_value(&[1, 5000], 0, 0x4000, 1)),
+            decimal("-1.5")
+        );
+    }
+
+    #[test]
+    fn numeric_trailing_zeros() {
+        // 12 000 000: the trailing zero digits aren't sent, the weight is
+        // above the last digit (a negative exponent).
+        let value = Binary::numeric(&numeric_value(&[1200], 1, 0, 0));
+
+        assert_eq!(value, decimal("12000000"));
+        assert_eq!(value.scale(), 0);
+    }
+
+    #[test]
+    fn numeric_zero() {
+        assert_eq!(Binary::numeric(&numeric_value(&[], 0, 0, 0)), decimal("0"));
+        assert_eq!(
+            Binary::numeric(&numeric_value(&[], 0, 0, 3)),
+            decimal("0.000")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN or infinite numeric")]
+    fn numeric_nan() {
+        Binary::numeric(&numeric_value(&[], 0, 0xC000, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN or infinite numeric")]
+    fn numeric_infinity() {
+        Binary::numeric(&numeric_value(&[], 0, 0xD000, 0));
+    }
+
+    /**
+     * Binary one dimension int4 array, `None` for null elements.
+     */
+    fn array_value(elements: &[Option<i32>]) -> Vec<u8> {
+        let mut value = Vec::new();
+        let has_null = elements.iter().any(Option::is_none);
+
+        value.extend_from_slice(&1_i32.to_be_bytes());
+        value.extend_from_slice(&(has_null as i32).to_be_bytes());
+        // int4 oid.
+        value.extend_from_slice(&23_i32.to_be_bytes());
+        value.extend_from_slice(&(elements.len() as i32).to_be_bytes());
+        value.extend_from_slice(&1_i32.to_be_bytes());
+
+        for element in elements {
+            match element {
+                Some(x) => {
+                    value.extend_from_slice(&4_i32.to_be_bytes());
+                    value.extend_from_slice(&x.to_be_bytes());
+                }
+                None => value.extend_from_slice(&(-1_i32).to_be_bytes()),
+            }
+        }
+
+        value
+    }
+
+    #[test]
+    fn array() {
+        let value = array_value(&[Some(1), Some(-2), Some(3)]);
+
+        assert_eq!(Binary::array(&value, Binary::i32), vec![1, -2, 3]);
+    }
+
+    #[test]
+    fn array_null() {
+        let value = array_value(&[Some(1), None, Some(3)]);
+
+        assert_eq!(Binary::array(&value, Binary::i32), vec![1, 3]);
+    }
+
+    #[test]
+    fn array_empty() {
+        // No dimension, nor element type.
+        let value = [0_i32, 0, 23]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect::<Vec<_>>();
+
+        assert!(Binary::array(&value, Binary::i32).is_empty());
+    }
+
+    fn range_value(flags: u8, bounds: &[i32]) -> Vec<u8> {
+        let mut value = vec![flags];
+
+        for bound in bounds {
+            value.extend_from_slice(&4_i32.to_be_bytes());
+            value.extend_from_slice(&bound.to_be_bytes());
+        }
+
+        value
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(
+            Binary::range(&range_value(0x02, &[1, 11])),
+            (Bound::Included(1), Bound::Excluded(11))
+        );
+        assert_eq!(
+            Binary::range(&range_value(0x06, &[-5, 5])),
+            (Bound::Included(-5), Bound::Included(5))
+        );
+    }
+
+    #[test]
+    fn range_infinite() {
+        assert_eq!(
+            Binary::range(&range_value(0x08, &[5])),
+            (Bound::Unbounded, Bound::Excluded(5))
+        );
+        assert_eq!(
+            Binary::range(&range_value(0x02 | 0x10, &[1])),
+            (Bound::Included(1), Bound::Unbounded)
+        );
+        assert_eq!(
+            Binary::range(&range_value(0x18, &[])),
+            (Bound::Unbounded, Bound::Unbounded)
+        );
+    }
+
+    #[test]
+    fn range_empty() {
+        assert_eq!(
+            Binary::range(&[0x01]),
+            (Bound::Excluded(0), Bound::Excluded(0))
+        );
+    }
+
+    #[test]
+    fn inet() {
+        // Family, netmask bits, cidr flag and address length.
+        let value = [2, 32, 0, 4, 10, 0, 0, 1];
+
+        assert_eq!(Binary::inet(&value), std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+        let mut value = vec![3, 64, 0, 16];
+        value.extend_from_slice(&std::net::Ipv6Addr::LOCALHOST.octets());
+
+        assert_eq!(Binary::inet(&value), std::net::Ipv6Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn interval() {
+        let mut value = Vec::new();
+        value.extend_from_slice(&(-3_000_000_i64).to_be_bytes());
+        value.extend_from_slice(&2_i32.to_be_bytes());
+        value.extend_from_slice(&14_i32.to_be_bytes());
+
+        let interval = Binary::interval(&value);
+
+        assert_eq!(interval.microseconds, -3_000_000);
+        assert_eq!(interval.days, 2);
+        assert_eq!(interval.months, 14);
+    }
+
+    #[test]
+    fn timestamptz() {
+        // 2024-01-01 00:00:00 UTC, in microseconds since 2000-01-01.
+        let value = 757_382_400_000_000_i64.to_be_bytes();
+
+        assert_eq!(
+            Binary::timestamptz(&value),
+            chrono::DateTime::from_timestamp(1_704_067_200, 0).unwrap()
+        );
+
+        // Before 2000.
+        let value = (-1_i64).to_be_bytes();
+
+        assert_eq!(
+            Binary::timestamptz(&value),
+            chrono::DateTime::from_timestamp(946_684_799, 999_999_000).unwrap()
+        );
+    }
+}
@@ -1,7 +1,8 @@
-#![feature(test)]
-#![allow(soft_unstable)]
+mod format;
+mod types;
 
-extern crate test;
+pub use format::{Binary, Format, Text};
+pub use types::{Interval, Mood, Range, Types};
 
 pub struct User {
     pub id: uuid::Uuid,
@@ -12,33 +13,24 @@ pub struct User {
 }
 
 impl User {
-    fn from(result: &libpq::Result, x: usize) -> libpq::errors::Result<User> {
+    fn from<F: Format>(result: &libpq::Result, x: usize) -> libpq::errors::Result<User> {
         let result = to_result(result)?;
 
-        let id = String::from_utf8(result.value(x, 0).unwrap().to_vec())
-            .unwrap()
-            .parse()
-            .unwrap();
-        let name = String::from_utf8(result.value(x, 1).unwrap().to_vec()).unwrap();
+        let id = F::uuid(result.value(x, 0).unwrap());
+        let name = F::string(result.value(x, 1).unwrap());
         let hair_color = if result.is_null(x, 2) {
             None
         } else {
-            String::from_utf8(result.value(x, 2).unwrap().to_vec()).ok()
+            Some(F::string(result.value(x, 2).unwrap()))
         };
         let created_at = if result.is_null(x, 3) {
             None
         } else {
-            let s = String::from_utf8(result.value(x, 3).unwrap().to_vec()).unwrap();
-            chrono::NaiveDateTime::parse_from_str(&s, "%F %T").ok()
+            Some(F::timestamp(result.value(x, 3).unwrap()))
         };
 
         let posts = if result.nfields() >= 5 {
-            String::from_utf8(result.value(x, 4).unwrap_or_default().to_vec())
-                .unwrap()
-                .trim_matches(|c| c == '{' || c == '}')
-                .split(",")
-                .map(|x| x.trim_matches('"').to_string())
-                .collect()
+            F::array(result.value(x, 4).unwrap_or_default(), F::string)
         } else {
             Vec::new()
         };
@@ -71,19 +63,84 @@ fn to_result(result: &libpq::Result) -> libpq::errors::Result<&libpq::Result> {
     }
 }
 
-struct Connection(libpq::Connection);
+/**
+ * Error of `copy_data` at the end of the data, the only one expected.
+ */
+const COPY_DONE: &str = "COPY is done";
+
+/**
+ * Client requesting its results in the `F` format.
+ */
+pub struct Connection<F: Format = Text> {
+    connection: libpq::Connection,
+    /// Set once the statement of the prepared lookup exists on the server.
+    prepared: bool,
+    format: std::marker::PhantomData<F>,
+}
+
+impl<F: Format> Connection<F> {
+    /**
+     * Runs a query without parameters returning rows, through the extended
+     * query protocol: the simple one only returns text results.
+     */
+    fn fetch(&self, query: &str) -> libpq::Result {
+        libpq::Connection::exec_params(&self.connection, query, &[], &[], &[], F::FORMAT)
+    }
+
+    /**
+     * Reads the page starting at `offset` through the `users_page` cursor,
+     * inside the transaction opened by the caller.
+     */
+    fn cursor_page(&self, offset: usize) -> libpq::errors::Result<libpq::Result> {
+        use elephantry_benchmark::PAGE_SIZE;
+
+        to_result(&libpq::Connection::exec(
+            &self.connection,
+            &format!(
+                "declare users_page cursor for select id, name, hair_color, created_at from users order by id;
+                move absolute {offset} in users_page"
+            ),
+        ))?;
+
+        let result = self.fetch(&format!("fetch {PAGE_SIZE} from users_page"));
+        to_result(&result)?;
+
+        Ok(result)
+    }
+
+    /**
+     * Runs a `COPY … FROM STDIN` statement with `data`.
+     */
+    fn copy(&self, statement: &str, data: &[u8]) -> libpq::errors::Result {
+        to_result(&libpq::Connection::exec(&self.connection, statement))?;
+
+        libpq::Connection::put_copy_data(&self.connection, data)?;
+        libpq::Connection::put_copy_end(&self.connection, None)?;
 
-impl elephantry_benchmark::Client for Connection {
+        while let Some(result) = libpq::Connection::result(&self.connection) {
+            to_result(&result)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Format> elephantry_benchmark::Client for Connection<F> {
     type Error = libpq::errors::Error;
     type User = User;
     type Post = String;
+    type Types = Types;
 
     fn create(dsn: &str) -> Result<Self, Self::Error> {
-        libpq::Connection::new(dsn).map(Self)
+        libpq::Connection::new(dsn).map(|connection| Self {
+            connection,
+            prepared: false,
+            format: std::marker::PhantomData,
+        })
     }
 
     fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
-        let result = libpq::Connection::exec(&self.0, &query);
+        let result = libpq::Connection::exec(&self.connection, query);
 
         to_result(&result).map(|_| ())
     }
@@ -93,13 +150,10 @@ impl elephantry_benchmark::Client for Connection {
         let hair_color = "hair color\0";
 
         let result = libpq::Connection::exec_params(
-            &self.0,
+            &self.connection,
             "insert into users (name, hair_color) values ($1, $2)",
             &[],
-            &[
-                Some(name.as_bytes()),
-                Some(hair_color.as_bytes()),
-            ],
+            &[Some(name.as_bytes()), Some(hair_color.as_bytes())],
             &[libpq::Format::Text, libpq::Format::Text],
             libpq::Format::Text,
         );
@@ -108,36 +162,27 @@ impl elephantry_benchmark::Client for Connection {
     }
 
     fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
-        let result = libpq::Connection::exec(
-            &self.0,
-            "select id, name, hair_color, created_at from users",
-        );
+        let result = self.fetch("select id, name, hair_color, created_at from users");
 
         let mut users = Vec::new();
 
         for x in 0..result.ntuples() {
-            users.push(User::from(&result, x)?);
+            users.push(User::from::<F>(&result, x)?);
         }
 
         Ok(users)
     }
 
     fn fetch_first(&mut self) -> Result<Self::User, Self::Error> {
-        let result = libpq::Connection::exec(
-            &self.0,
-            "select id, name, hair_color, created_at from users",
-        );
+        let result = self.fetch("select id, name, hair_color, created_at from users");
 
-        User::from(&result, 0)
+        User::from::<F>(&result, 0)
     }
 
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error> {
-        let result = libpq::Connection::exec(
-            &self.0,
-            "select id, name, hair_color, created_at from users",
-        );
+    fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
+        let result = self.fetch("select id, name, hair_color, created_at from users");
 
-        User::from(&result, 9_999)
+        User::from::<F>(&result, rows - 1)
     }
 
     fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error> {
@@ -145,7 +190,7 @@ impl elephantry_benchmark::Client for Connection {
         id.push(b'\0');
 
         let result = libpq::Connection::exec_params(
-            &self.0,
+            &self.connection,
             "select u.*, array_agg(p.title)
     from users u
     join posts p on p.author = u.id
@@ -154,10 +199,10 @@ impl elephantry_benchmark::Client for Connection {
             ",
             &[libpq::types::UUID.oid],
             &[Some(&id)],
-            &[libpq::Format::Text, libpq::Format::Text],
-            libpq::Format::Text,
+            &[libpq::Format::Text],
+            F::FORMAT,
         );
-        let user = User::from(&result, 0)?;
+        let user = User::from::<F>(&result, 0)?;
         let posts = user.posts.clone();
 
         Ok((user, posts))
@@ -165,7 +210,7 @@ impl elephantry_benchmark::Client for Connection {
 
     fn all_relations(&mut self) -> Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error> {
         let result = libpq::Connection::exec_params(
-            &self.0,
+            &self.connection,
             "select u.*, array_agg(p.title)
     from users u
     join posts p on p.author = u.id
@@ -174,13 +219,13 @@ impl elephantry_benchmark::Client for Connection {
             &[],
             &[],
             &[],
-            libpq::Format::Text,
+            F::FORMAT,
         );
 
         let mut users = Vec::new();
 
         for x in 0..result.ntuples() {
-            let user = User::from(&result, x)?;
+            let user = User::from::<F>(&result, x)?;
             let posts = user.posts.clone();
 
             users.push((user, posts));
@@ -188,6 +233,524 @@ impl elephantry_benchmark::Client for Connection {
 
         Ok(users)
     }
-}
 
-elephantry_benchmark::bench! {Connection}
+    fn update_user(&mut self) -> Result<(), Self::Error> {
+        let mut id = elephantry_benchmark::UUID.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+        let name = "User\0";
+        let hair_color = "hair color\0";
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "update users set name = $2, hair_color = $3 where id = $1",
+            &[
+                libpq::types::UUID.oid,
+                libpq::types::VARCHAR.oid,
+                libpq::types::VARCHAR.oid,
+            ],
+            &[
+                Some(&id),
+                Some(name.as_bytes()),
+                Some(hair_color.as_bytes()),
+            ],
+            &[
+                libpq::Format::Text,
+                libpq::Format::Text,
+                libpq::Format::Text,
+            ],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn update_users(&mut self) -> Result<(), Self::Error> {
+        let hair_color = "hair color\0";
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "update users set hair_color = $1",
+            &[],
+            &[Some(hair_color.as_bytes())],
+            &[libpq::Format::Text],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        let mut id = id.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "delete from users where id = $1",
+            &[libpq::types::UUID.oid],
+            &[Some(&id)],
+            &[libpq::Format::Text],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        let mut id = id.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+        let name = "User\0";
+        let hair_color = "hair color\0";
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "insert into users (id, name, hair_color) values ($1, $2, $3)
+                on conflict (id) do update set name = excluded.name, hair_color = excluded.hair_color",
+            &[],
+            &[Some(&id), Some(name.as_bytes()), Some(hair_color.as_bytes())],
+            &[libpq::Format::Text, libpq::Format::Text, libpq::Format::Text],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    /**
+     * The keys are sent as an array literal, `{id,id}`.
+     */
+    fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        let ids = ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let mut ids = format!("{{{}}}", ids.join(",")).into_bytes();
+        ids.push(b'\0');
+        let name = "User\0";
+        let hair_color = "hair color\0";
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "insert into users (id, name, hair_color) select id, $2, $3 from unnest($1::uuid[]) as id
+                on conflict (id) do update set name = excluded.name, hair_color = excluded.hair_color",
+            &[],
+            &[Some(&ids), Some(name.as_bytes()), Some(hair_color.as_bytes())],
+            &[libpq::Format::Text, libpq::Format::Text, libpq::Format::Text],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn transaction(&mut self) -> Result<(), Self::Error> {
+        let mut id = elephantry_benchmark::UUID.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+        let hair_color = "hair color\0";
+
+        self.exec("begin")?;
+        self.insert_user()?;
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "update users set hair_color = $2 where id = $1",
+            &[libpq::types::UUID.oid, libpq::types::VARCHAR.oid],
+            &[Some(&id), Some(hair_color.as_bytes())],
+            &[libpq::Format::Text, libpq::Format::Text],
+            libpq::Format::Text,
+        );
+        to_result(&result)?;
+
+        self.exec("commit")
+    }
+
+    fn rollback(&mut self) -> Result<(), Self::Error> {
+        self.exec("begin")?;
+        self.insert_user()?;
+        self.exec("rollback")
+    }
+
+    fn savepoints(&mut self) -> Result<(), Self::Error> {
+        self.exec("begin")?;
+        self.insert_user()?;
+        self.exec("savepoint savepoint_1")?;
+        self.insert_user()?;
+        self.exec("savepoint savepoint_2")?;
+        self.insert_user()?;
+        self.exec("rollback to savepoint savepoint_2")?;
+        self.exec("release savepoint savepoint_1")?;
+        self.exec("commit")
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+
+        let offset = (page - 1) * PAGE_SIZE;
+
+        let result = match pagination {
+            Pagination::Offset => {
+                let limit = format!("{PAGE_SIZE}\0");
+                let offset = format!("{offset}\0");
+
+                libpq::Connection::exec_params(
+                    &self.connection,
+                    "select id, name, hair_color, created_at from users order by id limit $1 offset $2",
+                    &[],
+                    &[Some(limit.as_bytes()), Some(offset.as_bytes())],
+                    &[libpq::Format::Text, libpq::Format::Text],
+                    F::FORMAT,
+                )
+            }
+            Pagination::Keyset => {
+                let mut id = elephantry_benchmark::page_key(page)
+                    .to_string()
+                    .as_bytes()
+                    .to_vec();
+                id.push(b'\0');
+                let limit = format!("{PAGE_SIZE}\0");
+
+                libpq::Connection::exec_params(
+                    &self.connection,
+                    "select id, name, hair_color, created_at from users where id > $1 order by id limit $2",
+                    &[],
+                    &[Some(&id), Some(limit.as_bytes())],
+                    &[libpq::Format::Text, libpq::Format::Text],
+                    F::FORMAT,
+                )
+            }
+            Pagination::Cursor => {
+                self.exec("begin")?;
+
+                match self.cursor_page(offset) {
+                    Ok(result) => {
+                        self.exec("commit")?;
+
+                        result
+                    }
+                    Err(err) => {
+                        // Closes the cursor and ends the aborted transaction,
+                        // the error worth reporting is the one of the page.
+                        self.exec("rollback").ok();
+
+                        return Err(err);
+                    }
+                }
+            }
+        };
+
+        let result = to_result(&result)?;
+
+        (0..result.ntuples())
+            .map(|x| User::from::<F>(result, x))
+            .collect()
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &elephantry_benchmark::Mode::ALL
+    }
+
+    fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+
+        let query = "select name from users where id = $1";
+        let mut id = elephantry_benchmark::UUID.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+
+        let result = match mode {
+            Mode::Simple => libpq::Connection::exec(
+                &self.connection,
+                &format!(
+                    "select name from users where id = '{}'",
+                    elephantry_benchmark::UUID
+                ),
+            ),
+            Mode::Unprepared => libpq::Connection::exec_params(
+                &self.connection,
+                query,
+                &[libpq::types::UUID.oid],
+                &[Some(&id)],
+                &[libpq::Format::Text],
+                F::FORMAT,
+            ),
+            Mode::Prepared => {
+                if !self.prepared {
+                    let result = libpq::Connection::prepare(
+                        &self.connection,
+                        Some("lookup"),
+                        query,
+                        &[libpq::types::UUID.oid],
+                    );
+                    to_result(&result)?;
+                    self.prepared = true;
+                }
+
+                libpq::Connection::exec_prepared(
+                    &self.connection,
+                    Some("lookup"),
+                    &[Some(&id)],
+                    &[libpq::Format::Text],
+                    F::FORMAT,
+                )
+            }
+        };
+
+        // Same representation of a varchar in both formats, the simple query
+        // always returns text.
+        Ok(F::string(to_result(&result)?.value(0, 0).unwrap()))
+    }
+
+    fn stream(&mut self) -> Result<usize, Self::Error> {
+        libpq::Connection::send_query_params(
+            &self.connection,
+            "select name from users",
+            &[],
+            &[],
+            &[],
+            F::FORMAT,
+        )?;
+        libpq::Connection::set_single_row_mode(&self.connection)?;
+
+        let mut len = 0;
+
+        // One result per row, then an empty one closing the result set.
+        while let Some(result) = libpq::Connection::result(&self.connection) {
+            if to_result(&result)?.status() == libpq::Status::SingleTuple {
+                len += std::str::from_utf8(result.value(0, 0).unwrap())
+                    .unwrap()
+                    .len();
+            }
+        }
+
+        Ok(len)
+    }
+
+    fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        let result = self.fetch("select * from types");
+        to_result(&result)?;
+
+        let types = (0..result.ntuples())
+            .map(|x| Types::from::<F>(&result, x))
+            .collect();
+
+        Ok(types)
+    }
+
+    fn insert_types(&mut self) -> Result<(), Self::Error> {
+        use libpq::types::*;
+
+        let params = types::params(&elephantry_benchmark::types::NewTypes::new());
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "insert into types (document, amount, data, integers, floats, created_at, duration, address, mood, period)
+    values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            &[
+                JSONB.oid,
+                NUMERIC.oid,
+                BYTEA.oid,
+                INT4_ARRAY.oid,
+                FLOAT8_ARRAY.oid,
+                TIMESTAMPTZ.oid,
+                INTERVAL.oid,
+                INET.oid,
+                // Inferred by the server, the enum has no fixed oid.
+                0,
+                INT4_RANGE.oid,
+            ],
+            &params.iter().map(|x| Some(x.as_slice())).collect::<Vec<_>>(),
+            &[libpq::Format::Text; 10],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        // Sent as is in the binary format, the text one would need the hex
+        // encoding.
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "insert into payloads (data) values ($1)",
+            &[libpq::types::BYTEA.oid],
+            &[Some(data)],
+            &[libpq::Format::Binary],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let result = self.fetch("select data from payloads where id = 1");
+        let data = F::bytea(to_result(&result)?.value(0, 0).unwrap());
+
+        Ok(data.len())
+    }
+
+    fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        let mut value = Vec::with_capacity(content.len() + 1);
+        value.extend_from_slice(content.as_bytes());
+        value.push(b'\0');
+
+        let result = libpq::Connection::exec_params(
+            &self.connection,
+            "insert into payloads (content) values ($1)",
+            &[libpq::types::TEXT.oid],
+            &[Some(&value)],
+            &[libpq::Format::Text],
+            libpq::Format::Text,
+        );
+
+        to_result(&result).map(|_| ())
+    }
+
+    fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let result = self.fetch("select content from payloads where id = 1");
+        let content = F::string(to_result(&result)?.value(0, 0).unwrap());
+
+        Ok(content.len())
+    }
+
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const { &[Implementation::LOOP, Implementation::optimized("copy")] }
+    }
+
+    fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "loop" => self.insert_users(n),
+            "copy" => self.copy_in(elephantry_benchmark::CopyFormat::Binary, n),
+            _ => unreachable!(),
+        }
+    }
+
+    fn pipeline_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const {
+            &[
+                Implementation::SERIAL,
+                Implementation::optimized("pipelined"),
+            ]
+        }
+    }
+
+    /**
+     * Sends every query in pipeline mode, then a sync, and only then reads
+     * the results: one per query, each followed by `None`, then the sync
+     * one.
+     */
+    fn pipeline(&mut self, implementation: &str) -> Result<usize, Self::Error> {
+        if implementation == "serial" {
+            return self.serial();
+        }
+
+        let name = "User\0";
+        let hair_color = "hair color\0";
+        let mut id = elephantry_benchmark::UUID.to_string().as_bytes().to_vec();
+        id.push(b'\0');
+
+        libpq::pipeline::enter(&self.connection)?;
+
+        for _ in 0..elephantry_benchmark::PIPELINE_SIZE {
+            self.connection.send_query_params(
+                "insert into users (name, hair_color) values ($1, $2)",
+                &[],
+                &[Some(name.as_bytes()), Some(hair_color.as_bytes())],
+                &[libpq::Format::Text, libpq::Format::Text],
+                libpq::Format::Text,
+            )?;
+        }
+
+        for _ in 0..elephantry_benchmark::PIPELINE_SIZE {
+            self.connection.send_query_params(
+                "select name from users where id = $1",
+                &[libpq::types::UUID.oid],
+                &[Some(&id)],
+                &[libpq::Format::Text],
+                F::FORMAT,
+            )?;
+        }
+
+        libpq::pipeline::sync(&self.connection)?;
+
+        let mut len = 0;
+
+        for x in 0..elephantry_benchmark::PIPELINE_SIZE * 2 {
+            let result = self.connection.result().unwrap();
+            to_result(&result)?;
+
+            if x >= elephantry_benchmark::PIPELINE_SIZE {
+                len += F::string(result.value(0, 0).unwrap()).len();
+            }
+
+            let end = self.connection.result();
+            debug_assert!(end.is_none());
+        }
+
+        let sync = self.connection.result().unwrap();
+        debug_assert_eq!(sync.status(), libpq::Status::PipelineSync);
+
+        libpq::pipeline::exit(&self.connection)?;
+
+        Ok(len)
+    }
+
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        self.copy(&format.copy_in_statement(), &format.users(n))
+    }
+
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    fn copy_out(&mut self, format: elephantry_benchmark::CopyFormat) -> Result<usize, Self::Error> {
+        self.exec(&format.copy_out_statement())?;
+
+        let mut len = 0;
+
+        // Rows until the end of the copy, then the result of the statement,
+        // which carries the error of the server if the copy failed.
+        loop {
+            match libpq::Connection::copy_data(&self.connection, false) {
+                Ok(row) => len += row.len(),
+                Err(libpq::errors::Error::Backend(message)) if message == COPY_DONE => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        while let Some(result) = libpq::Connection::result(&self.connection) {
+            let status = to_result(&result)?.status();
+
+            if status != libpq::Status::CommandOk {
+                return Err(libpq::errors::Error::Backend(format!(
+                    "unexpected COPY result: {status:?}"
+                )));
+            }
+        }
+
+        Ok(len)
+    }
+
+    fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        use elephantry_benchmark::dataset;
+
+        self.copy(dataset::USERS_COPY, &dataset::users_text(users))?;
+        self.copy(dataset::POSTS_COPY, &dataset::posts_text(posts))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::Format;
+use elephantry_benchmark::types::NewTypes;
+use std::ops::Bound;
+
+pub use elephantry_benchmark::types::{Interval, Mood};
+
+pub struct Types {
+    pub id: i32,
+    pub document: serde_json::Value,
+    pub amount: rust_decimal::Decimal,
+    pub data: Vec<u8>,
+    pub integers: Vec<i32>,
+    pub floats: Vec<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub duration: Interval,
+    pub address: std::net::IpAddr,
+    pub mood: Mood,
+    pub period: Range,
+}
+
+impl Types {
+    pub(crate) fn from<F: Format>(result: &libpq::Result, x: usize) -> Self {
+        let value = |column| result.value(x, column).unwrap();
+
+        Self {
+            id: F::i32(value(0)),
+            document: F::json(value(1)),
+            amount: F::numeric(value(2)),
+            data: F::bytea(value(3)),
+            integers: F::array(value(4), F::i32),
+            floats: F::array(value(5), F::f64),
+            created_at: F::timestamptz(value(6)),
+            duration: F::interval(value(7)),
+            address: F::inet(value(8)),
+            // Same representation of an enum in both formats.
+            mood: std::str::from_utf8(value(9)).unwrap().parse().unwrap(),
+            period: F::range(value(10)),
+        }
+    }
+}
+
+/**
+ * Values of the inserted columns, as null terminated strings.
+ */
+pub(crate) fn params(types: &NewTypes) -> Vec<Vec<u8>> {
+    let bound = |bound: Bound<i32>| match bound {
+        Bound::Included(x) | Bound::Excluded(x) => x.to_string(),
+        Bound::Unbounded => String::new(),
+    };
+    let list = |values: Vec<String>| format!("{{{}}}", values.join(","));
+
+    let params = [
+        types.document.to_string(),
+        rust_decimal::Decimal::new(types.amount.0, types.amount.1).to_string(),
+        format!(
+            "\\x{}",
+            types
+                .data
+                .iter()
+                .map(|x| format!("{x:02x}"))
+                .collect::<String>()
+        ),
+        list(types.integers.iter().map(ToString::to_string).collect()),
+        list(types.floats.iter().map(ToString::to_string).collect()),
+        chrono::DateTime::from_timestamp(types.created_at, 0)
+            .unwrap()
+            .to_rfc3339(),
+        format!(
+            "{} mons {} days {} microseconds",
+            types.duration.months, types.duration.days, types.duration.microseconds
+        ),
+        types.address.to_string(),
+        format!("{:?}", types.mood),
+        format!(
+            "{}{},{}{}",
+            if matches!(types.period.0, Bound::Included(_)) {
+                '['
+            } else {
+                '('
+            },
+            bound(types.period.0),
+            bound(types.period.1),
+            if matches!(types.period.1, Bound::Included(_)) {
+                ']'
+            } else {
+                ')'
+            },
+        ),
+    ];
+
+    params
+        .into_iter()
+        .map(|x| {
+            let mut x = x.into_bytes();
+            x.push(b'\0');
+            x
+        })
+        .collect()
+}
+
+/**
+ * int4range, an empty range is `(Excluded(0), Excluded(0))`.
+ */
+pub type Range = (Bound<i32>, Bound<i32>);
@@ -0,0 +1 @@
+elephantry_benchmark::bench! {postgres_bench::Connection}
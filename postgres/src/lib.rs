@@ -1,14 +1,11 @@
-#![feature(test)]
-#![allow(soft_unstable)]
-
-extern crate test;
+pub use elephantry_benchmark::types::{Interval, Mood, Range, Types};
 
 pub struct User {
-    id: uuid::Uuid,
-    name: String,
-    hair_color: Option<String>,
-    created_at: chrono::NaiveDateTime,
-    posts: Option<Vec<Post>>,
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub hair_color: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub posts: Option<Vec<Post>>,
 }
 
 impl User {
@@ -25,20 +22,32 @@ impl User {
 
 #[derive(Clone, postgres_derive::FromSql)]
 pub struct Post {
-    id: Option<uuid::Uuid>,
-    title: String,
-    content: String,
+    pub id: Option<uuid::Uuid>,
+    pub title: String,
+    pub content: String,
+}
+
+/**
+ * Gives back the error of the server the COPY reader and writer wrap in an
+ * `io::Error`: they only fail with one.
+ */
+fn from_io(err: std::io::Error) -> postgres::Error {
+    match err.into_inner().map(|x| x.downcast::<postgres::Error>()) {
+        Some(Ok(err)) => *err,
+        _ => unreachable!("COPY streams only fail with postgres errors"),
+    }
 }
 
-struct Connection(postgres::Client);
+pub struct Connection(postgres::Client, Option<postgres::Statement>);
 
 impl elephantry_benchmark::Client for Connection {
     type Error = postgres::Error;
     type User = User;
     type Post = Post;
+    type Types = Types;
 
     fn create(dsn: &str) -> Result<Self, Self::Error> {
-        postgres::Client::connect(dsn, postgres::NoTls).map(Self)
+        postgres::Client::connect(dsn, postgres::NoTls).map(|x| Self(x, None))
     }
 
     fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
@@ -47,10 +56,10 @@ impl elephantry_benchmark::Client for Connection {
 
     fn insert_user(&mut self) -> Result<(), Self::Error> {
         self.0
-            .execute("INSERT INTO users (name, hair_color) VALUES ($1, $2)", &[
-                &"User".to_string(),
-                &"hair color".to_string(),
-            ])
+            .execute(
+                "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+                &[&"User".to_string(), &"hair color".to_string()],
+            )
             .map(|_| ())
     }
 
@@ -77,13 +86,13 @@ impl elephantry_benchmark::Client for Connection {
         Ok(result)
     }
 
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error> {
+    fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
         let result = self
             .0
             .query("SELECT id, name, hair_color, created_at FROM users", &[])?
             .iter()
             .map(User::from_row)
-            .nth(9_999)
+            .nth(rows - 1)
             .unwrap();
 
         Ok(result)
@@ -132,6 +141,337 @@ select u.*, array_agg(p)
 
         Ok(users)
     }
-}
 
-elephantry_benchmark::bench! {Connection}
+    fn update_user(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "UPDATE users SET name = $2, hair_color = $3 WHERE id = $1",
+                &[&elephantry_benchmark::UUID, &"User", &"hair color"],
+            )
+            .map(|_| ())
+    }
+
+    fn update_users(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .execute("UPDATE users SET hair_color = $1", &[&"hair color"])
+            .map(|_| ())
+    }
+
+    fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .execute("DELETE FROM users WHERE id = $1", &[&id])
+            .map(|_| ())
+    }
+
+    fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "INSERT INTO users (id, name, hair_color) VALUES ($1, $2, $3)
+                    ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+                &[&id, &"User", &"hair color"],
+            )
+            .map(|_| ())
+    }
+
+    fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "INSERT INTO users (id, name, hair_color) SELECT id, $2, $3 FROM unnest($1::uuid[]) AS id
+                    ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+                &[&ids, &"User", &"hair color"],
+            )
+            .map(|_| ())
+    }
+
+    fn transaction(&mut self) -> Result<(), Self::Error> {
+        let mut transaction = self.0.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+            &[&"User", &"hair color"],
+        )?;
+        transaction.execute(
+            "UPDATE users SET hair_color = $2 WHERE id = $1",
+            &[&elephantry_benchmark::UUID, &"hair color"],
+        )?;
+
+        transaction.commit()
+    }
+
+    fn rollback(&mut self) -> Result<(), Self::Error> {
+        let mut transaction = self.0.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+            &[&"User", &"hair color"],
+        )?;
+
+        transaction.rollback()
+    }
+
+    fn savepoints(&mut self) -> Result<(), Self::Error> {
+        let query = "INSERT INTO users (name, hair_color) VALUES ($1, $2)";
+
+        let mut transaction = self.0.transaction()?;
+        transaction.execute(query, &[&"User", &"hair color"])?;
+
+        let mut outer = transaction.transaction()?;
+        outer.execute(query, &[&"User", &"hair color"])?;
+
+        let mut inner = outer.transaction()?;
+        inner.execute(query, &[&"User", &"hair color"])?;
+        inner.rollback()?;
+
+        outer.commit()?;
+        transaction.commit()
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+
+        let offset = (page - 1) * PAGE_SIZE;
+
+        let rows = match pagination {
+            Pagination::Offset => self.0.query(
+                "SELECT id, name, hair_color, created_at FROM users ORDER BY id LIMIT $1 OFFSET $2",
+                &[&(PAGE_SIZE as i64), &(offset as i64)],
+            )?,
+            Pagination::Keyset => self.0.query(
+                "SELECT id, name, hair_color, created_at FROM users WHERE id > $1 ORDER BY id LIMIT $2",
+                &[&elephantry_benchmark::page_key(page), &(PAGE_SIZE as i64)],
+            )?,
+            Pagination::Cursor => {
+                let mut transaction = self.0.transaction()?;
+
+                transaction.batch_execute(&format!(
+                    "DECLARE users_page CURSOR FOR SELECT id, name, hair_color, created_at FROM users ORDER BY id;
+                    MOVE ABSOLUTE {offset} IN users_page"
+                ))?;
+                let rows = transaction.query(&format!("FETCH {PAGE_SIZE} FROM users_page"), &[])?;
+                transaction.commit()?;
+
+                rows
+            }
+        };
+
+        Ok(rows.iter().map(User::from_row).collect())
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &elephantry_benchmark::Mode::ALL
+    }
+
+    fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+
+        let query = "SELECT name FROM users WHERE id = $1";
+
+        match mode {
+            Mode::Simple => {
+                let query = format!(
+                    "SELECT name FROM users WHERE id = '{}'",
+                    elephantry_benchmark::UUID
+                );
+                let name = self
+                    .0
+                    .simple_query(&query)?
+                    .into_iter()
+                    .find_map(|x| match x {
+                        postgres::SimpleQueryMessage::Row(row) => row.get(0).map(String::from),
+                        _ => None,
+                    })
+                    .unwrap();
+
+                Ok(name)
+            }
+            Mode::Unprepared => self
+                .0
+                .query_typed_one(
+                    query,
+                    &[(&elephantry_benchmark::UUID, postgres::types::Type::UUID)],
+                )
+                .map(|x| x.get(0)),
+            Mode::Prepared => {
+                let statement = match &self.1 {
+                    Some(statement) => statement.clone(),
+                    None => self.1.insert(self.0.prepare(query)?).clone(),
+                };
+
+                self.0
+                    .query_one(&statement, &[&elephantry_benchmark::UUID])
+                    .map(|x| x.get(0))
+            }
+        }
+    }
+
+    fn stream(&mut self) -> Result<usize, Self::Error> {
+        use postgres::fallible_iterator::FallibleIterator;
+
+        self.0
+            .query_raw("SELECT name FROM users", std::iter::empty::<i32>())?
+            .fold(0, |len, row| Ok(len + row.get::<_, &str>(0).len()))
+    }
+
+    fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        let results = self
+            .0
+            .query("SELECT * FROM types", &[])?
+            .iter()
+            .map(Types::from_row)
+            .collect();
+
+        Ok(results)
+    }
+
+    fn insert_types(&mut self) -> Result<(), Self::Error> {
+        let types = Types::new();
+
+        self.0
+            .execute(
+                "INSERT INTO types (document, amount, data, integers, floats, created_at, duration, address, mood, period)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                &[
+                    &types.document,
+                    &types.amount,
+                    &types.data,
+                    &types.integers,
+                    &types.floats,
+                    &types.created_at,
+                    &types.duration,
+                    &types.address,
+                    &types.mood,
+                    &types.period,
+                ],
+            )
+            .map(|_| ())
+    }
+
+    fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0
+            .execute("INSERT INTO payloads (data) VALUES ($1)", &[&data])
+            .map(|_| ())
+    }
+
+    fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let data: Vec<u8> = self
+            .0
+            .query_one("SELECT data FROM payloads WHERE id = 1", &[])?
+            .get(0);
+
+        Ok(data.len())
+    }
+
+    fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.0
+            .execute("INSERT INTO payloads (content) VALUES ($1)", &[&content])
+            .map(|_| ())
+    }
+
+    fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let content: String = self
+            .0
+            .query_one("SELECT content FROM payloads WHERE id = 1", &[])?
+            .get(0);
+
+        Ok(content.len())
+    }
+
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const { &[Implementation::LOOP, Implementation::optimized("copy")] }
+    }
+
+    fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "loop" => self.insert_users(n),
+            "copy" => self.copy_in(elephantry_benchmark::CopyFormat::Binary, n),
+            _ => unreachable!(),
+        }
+    }
+
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        use postgres::types::Type;
+        use std::io::Write;
+
+        let mut writer = self.0.copy_in(&format.copy_in_statement())?;
+
+        if format == elephantry_benchmark::CopyFormat::Binary {
+            let mut writer = postgres::binary_copy::BinaryCopyInWriter::new(
+                writer,
+                &[Type::VARCHAR, Type::VARCHAR],
+            );
+
+            for _ in 0..n {
+                writer.write(&[&"User", &"hair color"])?;
+            }
+
+            writer.finish().map(|_| ())
+        } else {
+            writer.write_all(&format.users(n)).map_err(from_io)?;
+
+            writer.finish().map(|_| ())
+        }
+    }
+
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    fn copy_out(&mut self, format: elephantry_benchmark::CopyFormat) -> Result<usize, Self::Error> {
+        use std::io::BufRead;
+
+        let mut reader = self.0.copy_out(&format.copy_out_statement())?;
+        let mut len = 0;
+
+        loop {
+            let chunk = reader.fill_buf().map_err(from_io)?.len();
+
+            if chunk == 0 {
+                break;
+            }
+
+            len += chunk;
+            reader.consume(chunk);
+        }
+
+        Ok(len)
+    }
+
+    fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        use elephantry_benchmark::dataset;
+        use std::io::Write;
+
+        for (statement, data) in [
+            (dataset::USERS_COPY, dataset::users_text(users)),
+            (dataset::POSTS_COPY, dataset::posts_text(posts)),
+        ] {
+            let mut writer = self.0.copy_in(statement)?;
+
+            writer.write_all(&data).map_err(from_io)?;
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1 @@
+elephantry_benchmark::bench_async! {sqlx_bench::Connection}
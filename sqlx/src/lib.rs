@@ -1,8 +1,3 @@
-#![feature(test)]
-#![allow(soft_unstable)]
-
-extern crate test;
-
 #[derive(Clone, sqlx::FromRow)]
 pub struct User {
     pub id: Option<uuid::Uuid>,
@@ -18,7 +13,7 @@ pub struct User {
 pub struct Posts(Vec<Post>);
 
 impl Posts {
-    fn to_vec(self) -> Vec<Post> {
+    fn into_vec(self) -> Vec<Post> {
         self.0
     }
 }
@@ -31,79 +26,157 @@ pub struct Post {
     pub content: String,
 }
 
-struct Connection(sqlx::PgConnection);
+#[derive(sqlx::FromRow)]
+pub struct Types {
+    pub id: Option<i32>,
+    pub document: sqlx::types::JsonValue,
+    pub amount: sqlx::types::Decimal,
+    pub data: Vec<u8>,
+    pub integers: Vec<i32>,
+    pub floats: Vec<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub duration: sqlx::postgres::types::PgInterval,
+    pub address: std::net::IpAddr,
+    pub mood: Mood,
+    pub period: sqlx::postgres::types::PgRange<i32>,
+}
+
+impl Types {
+    pub fn new() -> Self {
+        elephantry_benchmark::types::NewTypes::new().into()
+    }
+}
+
+impl From<elephantry_benchmark::types::NewTypes> for Types {
+    fn from(types: elephantry_benchmark::types::NewTypes) -> Self {
+        Self {
+            id: None,
+            document: types.document,
+            amount: sqlx::types::Decimal::new(types.amount.0, types.amount.1),
+            data: types.data,
+            integers: types.integers,
+            floats: types.floats,
+            created_at: chrono::DateTime::from_timestamp(types.created_at, 0).unwrap(),
+            duration: sqlx::postgres::types::PgInterval {
+                months: types.duration.months,
+                days: types.duration.days,
+                microseconds: types.duration.microseconds,
+            },
+            address: types.address,
+            mood: types.mood.into(),
+            period: types.period.into(),
+        }
+    }
+}
+
+impl Default for Types {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, sqlx::Type)]
+#[sqlx(type_name = "mood")]
+pub enum Mood {
+    Sad,
+    Ok,
+    Happy,
+}
+
+impl From<elephantry_benchmark::types::Mood> for Mood {
+    fn from(mood: elephantry_benchmark::types::Mood) -> Self {
+        use elephantry_benchmark::types;
 
-impl elephantry_benchmark::Client for Connection {
+        match mood {
+            types::Mood::Sad => Self::Sad,
+            types::Mood::Ok => Self::Ok,
+            types::Mood::Happy => Self::Happy,
+        }
+    }
+}
+
+pub struct Connection(sqlx::PgConnection);
+
+impl elephantry_benchmark::AsyncClient for Connection {
     type Error = sqlx::Error;
     type User = User;
     type Post = Post;
+    type Types = Types;
+
+    async fn create(dsn: &str) -> Result<Self, Self::Error> {
+        use sqlx::Connection;
 
-    fn create(dsn: &str) -> Result<Self, Self::Error> {
-        smol::block_on(async {
-            use sqlx::Connection;
-            sqlx::PgConnection::connect(dsn).await.map(Self)
-        })
+        sqlx::PgConnection::connect(dsn).await.map(Self)
     }
 
-    fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
+    async fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
         use sqlx::Executor;
-        smol::block_on(self.0.execute(query)).map(|_| ())
-    }
 
-    fn insert_user(&mut self) -> Result<(), Self::Error> {
-        smol::block_on({
-            sqlx::query("INSERT INTO users (name, hair_color) VALUES ($1, $2)")
-                .bind("User")
-                .bind("hair color")
-                .execute(&mut self.0)
-        })
-        .map(|_| ())
+        self.0.execute(query).await.map(|_| ())
     }
 
-    fn insert_users(&mut self, n: usize) -> Result<(), Self::Error> {
-        let names = vec!["User"; n];
-        let colors = vec!["hair color"; n];
-        smol::block_on({
-            sqlx::query(
-                "INSERT INTO users (name, hair_color) select * from unnest($1::text[], $2::text[])",
-            )
-            .bind(&names)
-            .bind(&colors)
+    async fn insert_user(&mut self) -> Result<(), Self::Error> {
+        sqlx::query("INSERT INTO users (name, hair_color) VALUES ($1, $2)")
+            .bind("User")
+            .bind("hair color")
             .execute(&mut self.0)
-        })
-        .map(|_| ())
+            .await
+            .map(|_| ())
     }
 
-    fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
-        smol::block_on({
-            sqlx::query_as::<_, User>(
-                "SELECT id, name, hair_color, created_at, null as posts FROM users",
-            )
-            .fetch_all(&mut self.0)
-        })
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const { &[Implementation::LOOP, Implementation::optimized("unnest")] }
     }
 
-    fn fetch_first(&mut self) -> Result<Self::User, Self::Error> {
-        smol::block_on({
-            sqlx::query_as::<_, User>(
-                "SELECT id, name, hair_color, created_at, null as posts FROM users",
-            )
-            .fetch_one(&mut self.0)
-        })
+    async fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "loop" => self.insert_users(n).await,
+            "unnest" => {
+                let names = vec!["User"; n];
+                let colors = vec!["hair color"; n];
+
+                sqlx::query(
+                    "INSERT INTO users (name, hair_color) select * from unnest($1::text[], $2::text[])",
+                )
+                .bind(&names)
+                .bind(&colors)
+                .execute(&mut self.0)
+                .await
+                .map(|_| ())
+            }
+            _ => unreachable!(),
+        }
     }
 
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error> {
-        let results = smol::block_on({
-            sqlx::query_as::<_, User>(
-                "SELECT id, name, hair_color, created_at, null as posts FROM users",
-            )
-            .fetch_all(&mut self.0)
-        })?;
+    async fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
+        sqlx::query_as::<_, User>(
+            "SELECT id, name, hair_color, created_at, null as posts FROM users",
+        )
+        .fetch_all(&mut self.0)
+        .await
+    }
 
-        Ok(results[9_999].clone())
+    async fn fetch_first(&mut self) -> Result<Self::User, Self::Error> {
+        sqlx::query_as::<_, User>(
+            "SELECT id, name, hair_color, created_at, null as posts FROM users",
+        )
+        .fetch_one(&mut self.0)
+        .await
     }
 
-    fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error> {
+    async fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
+        let results = sqlx::query_as::<_, User>(
+            "SELECT id, name, hair_color, created_at, null as posts FROM users",
+        )
+        .fetch_all(&mut self.0)
+        .await?;
+
+        Ok(results[rows - 1].clone())
+    }
+
+    async fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error> {
         let query = r#"
 select u.*, array_agg(p) as posts
     from users u
@@ -111,31 +184,357 @@ select u.*, array_agg(p) as posts
     where u.id = $1
     group by u.id, u.name, u.hair_color, u.created_at
 "#;
-        let user = smol::block_on({
-            sqlx::query_as::<_, User>(query)
-                .bind(elephantry_benchmark::UUID)
-                .fetch_one(&mut self.0)
-        })?;
-        let posts = user.posts.clone().map(Posts::to_vec).unwrap();
+        let user = sqlx::query_as::<_, User>(query)
+            .bind(elephantry_benchmark::UUID)
+            .fetch_one(&mut self.0)
+            .await?;
+        let posts = user.posts.clone().map(Posts::into_vec).unwrap();
 
         Ok((user, posts))
     }
 
-    fn all_relations(&mut self) -> Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error> {
+    async fn all_relations(&mut self) -> Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error> {
         let query = r#"
 select u.*, array_agg(p) as posts
     from users u
     join posts p on p.author = u.id
     group by u.id, u.name, u.hair_color, u.created_at
 "#;
-        let users =
-            smol::block_on(sqlx::query_as::<_, User>(query).fetch_all(&mut self.0))?
-                .iter()
-                .map(|u| (u.clone(), u.posts.clone().map(Posts::to_vec).unwrap()))
-                .collect();
+        let users = sqlx::query_as::<_, User>(query)
+            .fetch_all(&mut self.0)
+            .await?
+            .iter()
+            .map(|u| (u.clone(), u.posts.clone().map(Posts::into_vec).unwrap()))
+            .collect();
 
         Ok(users)
     }
-}
 
-elephantry_benchmark::bench! {Connection}
+    async fn update_user(&mut self) -> Result<(), Self::Error> {
+        sqlx::query("UPDATE users SET name = $2, hair_color = $3 WHERE id = $1")
+            .bind(elephantry_benchmark::UUID)
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut self.0)
+            .await
+            .map(|_| ())
+    }
+
+    async fn update_users(&mut self) -> Result<(), Self::Error> {
+        sqlx::query("UPDATE users SET hair_color = $1")
+            .bind("hair color")
+            .execute(&mut self.0)
+            .await
+            .map(|_| ())
+    }
+
+    async fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(id)
+            .execute(&mut self.0)
+            .await
+            .map(|_| ())
+    }
+
+    async fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        sqlx::query(
+            "INSERT INTO users (id, name, hair_color) VALUES ($1, $2, $3)
+                ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+        )
+        .bind(id)
+        .bind("User")
+        .bind("hair color")
+        .execute(&mut self.0)
+        .await
+        .map(|_| ())
+    }
+
+    async fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        sqlx::query(
+            "INSERT INTO users (id, name, hair_color) SELECT id, $2, $3 FROM unnest($1::uuid[]) AS id
+                ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+        )
+        .bind(ids)
+        .bind("User")
+        .bind("hair color")
+        .execute(&mut self.0)
+        .await
+        .map(|_| ())
+    }
+
+    async fn transaction(&mut self) -> Result<(), Self::Error> {
+        use sqlx::Connection;
+
+        let mut transaction = self.0.begin().await?;
+
+        sqlx::query("INSERT INTO users (name, hair_color) VALUES ($1, $2)")
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE users SET hair_color = $2 WHERE id = $1")
+            .bind(elephantry_benchmark::UUID)
+            .bind("hair color")
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await
+    }
+
+    async fn rollback(&mut self) -> Result<(), Self::Error> {
+        use sqlx::Connection;
+
+        let mut transaction = self.0.begin().await?;
+
+        sqlx::query("INSERT INTO users (name, hair_color) VALUES ($1, $2)")
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.rollback().await
+    }
+
+    async fn savepoints(&mut self) -> Result<(), Self::Error> {
+        use sqlx::Connection;
+
+        let query = "INSERT INTO users (name, hair_color) VALUES ($1, $2)";
+
+        let mut transaction = self.0.begin().await?;
+        sqlx::query(query)
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut *transaction)
+            .await?;
+
+        let mut outer = transaction.begin().await?;
+        sqlx::query(query)
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut *outer)
+            .await?;
+
+        let mut inner = outer.begin().await?;
+        sqlx::query(query)
+            .bind("User")
+            .bind("hair color")
+            .execute(&mut *inner)
+            .await?;
+        inner.rollback().await?;
+
+        outer.commit().await?;
+        transaction.commit().await
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    async fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+        use sqlx::{Connection, Executor};
+
+        let offset = (page - 1) * PAGE_SIZE;
+
+        match pagination {
+            Pagination::Offset => {
+                sqlx::query_as::<_, User>(
+                    "SELECT id, name, hair_color, created_at, null as posts FROM users ORDER BY id LIMIT $1 OFFSET $2",
+                )
+                .bind(PAGE_SIZE as i64)
+                .bind(offset as i64)
+                .fetch_all(&mut self.0)
+                .await
+            }
+            Pagination::Keyset => {
+                sqlx::query_as::<_, User>(
+                    "SELECT id, name, hair_color, created_at, null as posts FROM users WHERE id > $1 ORDER BY id LIMIT $2",
+                )
+                .bind(elephantry_benchmark::page_key(page))
+                .bind(PAGE_SIZE as i64)
+                .fetch_all(&mut self.0)
+                .await
+            }
+            Pagination::Cursor => {
+                let mut transaction = self.0.begin().await?;
+
+                transaction
+                    .execute(
+                        format!(
+                            "DECLARE users_page CURSOR FOR SELECT id, name, hair_color, created_at, null as posts FROM users ORDER BY id;
+                            MOVE ABSOLUTE {offset} IN users_page"
+                        )
+                        .as_str(),
+                    )
+                    .await?;
+                let users = sqlx::query_as::<_, User>(&format!("FETCH {PAGE_SIZE} FROM users_page"))
+                    .fetch_all(&mut *transaction)
+                    .await?;
+                transaction.commit().await?;
+
+                Ok(users)
+            }
+        }
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &elephantry_benchmark::Mode::ALL
+    }
+
+    async fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+        use sqlx::{Executor, Row};
+
+        let query = "SELECT name FROM users WHERE id = $1";
+
+        match mode {
+            Mode::Simple => {
+                let query = format!(
+                    "SELECT name FROM users WHERE id = '{}'",
+                    elephantry_benchmark::UUID
+                );
+
+                // A query without arguments uses the simple query protocol.
+                self.0.fetch_one(query.as_str()).await?.try_get(0)
+            }
+            Mode::Unprepared => {
+                sqlx::query_scalar(query)
+                    .bind(elephantry_benchmark::UUID)
+                    .persistent(false)
+                    .fetch_one(&mut self.0)
+                    .await
+            }
+            Mode::Prepared => {
+                sqlx::query_scalar(query)
+                    .bind(elephantry_benchmark::UUID)
+                    .fetch_one(&mut self.0)
+                    .await
+            }
+        }
+    }
+
+    async fn stream(&mut self) -> Result<usize, Self::Error> {
+        use futures_util::TryStreamExt;
+
+        sqlx::query_scalar::<_, String>("SELECT name FROM users")
+            .fetch(&mut self.0)
+            .try_fold(0, |len, name| async move { Ok(len + name.len()) })
+            .await
+    }
+
+    async fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        sqlx::query_as("SELECT * FROM types")
+            .fetch_all(&mut self.0)
+            .await
+    }
+
+    async fn insert_types(&mut self) -> Result<(), Self::Error> {
+        let types = Types::new();
+
+        sqlx::query(
+            "INSERT INTO types (document, amount, data, integers, floats, created_at, duration, address, mood, period)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(types.document)
+        .bind(types.amount)
+        .bind(types.data)
+        .bind(types.integers)
+        .bind(types.floats)
+        .bind(types.created_at)
+        .bind(types.duration)
+        .bind(types.address)
+        .bind(types.mood)
+        .bind(types.period)
+        .execute(&mut self.0)
+        .await
+        .map(|_| ())
+    }
+
+    async fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        sqlx::query("INSERT INTO payloads (data) VALUES ($1)")
+            .bind(data)
+            .execute(&mut self.0)
+            .await
+            .map(|_| ())
+    }
+
+    async fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let data: Vec<u8> = sqlx::query_scalar("SELECT data FROM payloads WHERE id = 1")
+            .fetch_one(&mut self.0)
+            .await?;
+
+        Ok(data.len())
+    }
+
+    async fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        sqlx::query("INSERT INTO payloads (content) VALUES ($1)")
+            .bind(content)
+            .execute(&mut self.0)
+            .await
+            .map(|_| ())
+    }
+
+    async fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let content: String = sqlx::query_scalar("SELECT content FROM payloads WHERE id = 1")
+            .fetch_one(&mut self.0)
+            .await?;
+
+        Ok(content.len())
+    }
+
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    async fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        let mut copy = self.0.copy_in_raw(&format.copy_in_statement()).await?;
+
+        copy.send(format.users(n)).await?;
+        copy.finish().await.map(|_| ())
+    }
+
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    async fn copy_out(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+    ) -> Result<usize, Self::Error> {
+        use futures_util::TryStreamExt;
+
+        self.0
+            .copy_out_raw(&format.copy_out_statement())
+            .await?
+            .try_fold(0, |len, chunk| async move { Ok(len + chunk.len()) })
+            .await
+    }
+
+    async fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        use elephantry_benchmark::dataset;
+
+        for (statement, data) in [
+            (dataset::USERS_COPY, dataset::users_text(users)),
+            (dataset::POSTS_COPY, dataset::posts_text(posts)),
+        ] {
+            let mut copy = self.0.copy_in_raw(statement).await?;
+
+            copy.send(data).await?;
+            copy.finish().await?;
+        }
+
+        Ok(())
+    }
+}
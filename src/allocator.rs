@@ -0,0 +1,140 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Global allocator counting the allocations of the whole process (all
+ * threads) before forwarding them to the system allocator.
+ *
+ * It's installed in the bench binaries by the `bench!` macros when the
+ * `alloc` feature is enabled (`cargo bench --features
+ * elephantry-benchmark/alloc`).
+ */
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+
+        if !new_ptr.is_null() {
+            // A reallocation is counted as a new allocation of `new_size`
+            // bytes, which replaces the old one.
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+
+        new_ptr
+    }
+}
+
+fn allocated(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(size as u64, Ordering::Relaxed);
+
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+/**
+ * Is the counting allocator installed in this binary?
+ */
+pub fn enabled() -> bool {
+    cfg!(feature = "alloc")
+}
+
+/**
+ * Allocations made during one iteration of a benchmark.
+ */
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Allocations {
+    /**
+     * Number of allocations (and reallocations).
+     */
+    pub count: u64,
+    /**
+     * Total of allocated bytes.
+     */
+    pub bytes: u64,
+    /**
+     * Maximum of bytes allocated at the same time, above what was already
+     * allocated at the start of the iteration.
+     */
+    pub peak: u64,
+}
+
+/**
+ * Counts the allocations from its creation.
+ */
+pub struct Counter {
+    allocations: u64,
+    bytes: u64,
+    current: usize,
+}
+
+impl Counter {
+    pub fn start() -> Self {
+        let current = CURRENT.load(Ordering::Relaxed);
+        PEAK.store(current, Ordering::Relaxed);
+
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: BYTES.load(Ordering::Relaxed),
+            current,
+        }
+    }
+
+    pub fn stop(self) -> Allocations {
+        Allocations {
+            count: ALLOCATIONS.load(Ordering::Relaxed) - self.allocations,
+            bytes: BYTES.load(Ordering::Relaxed) - self.bytes,
+            peak: PEAK.load(Ordering::Relaxed).saturating_sub(self.current) as u64,
+        }
+    }
+}
+
+/**
+ * Formats a number of bytes with the most readable binary unit.
+ */
+pub fn format_bytes(bytes: u64) -> String {
+    let bytes = bytes as f64;
+
+    if bytes < 1024. {
+        format!("{bytes} B")
+    } else if bytes < 1024. * 1024. {
+        format!("{:.2} KiB", bytes / 1024.)
+    } else if bytes < 1024. * 1024. * 1024. {
+        format!("{:.2} MiB", bytes / 1024. / 1024.)
+    } else {
+        format!("{:.2} GiB", bytes / 1024. / 1024. / 1024.)
+    }
+}
@@ -0,0 +1,480 @@
+use elephantry_benchmark::results::{Record, Results, Style};
+use elephantry_benchmark::stats::format_duration;
+use std::fmt::Write;
+use std::path::Path;
+
+const WIDTH: f64 = 1000.;
+const HEIGHT: f64 = 600.;
+const MARGIN_TOP: f64 = 50.;
+const MARGIN_RIGHT: f64 = 240.;
+const MARGIN_BOTTOM: f64 = 130.;
+const MARGIN_LEFT: f64 = 90.;
+
+const COLORS: [&str; 8] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a6761d", "#f781bf", "#999999",
+];
+
+const FONT: &str = "DejaVu Sans, Liberation Sans, Arial, sans-serif";
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options {
+    /**
+     * Uses a logarithmic duration axis.
+     */
+    pub log_scale: bool,
+    /**
+     * Divides the durations of each group by the fastest one.
+     */
+    pub relative: bool,
+}
+
+/**
+ * One bar: the median duration and its error bar (± standard deviation).
+ */
+struct Bar {
+    value: f64,
+    error: f64,
+}
+
+/**
+ * Bars of each series for one benchmark.
+ */
+struct Group {
+    label: String,
+    bars: Vec<Option<Bar>>,
+}
+
+/**
+ * A client, or one of its implementations of an operation, see
+ * [`Record::series`].
+ */
+struct Series {
+    label: String,
+    /**
+     * Index of the client, the implementations of a client share its color.
+     */
+    client: usize,
+    style: Option<Style>,
+}
+
+impl Series {
+    fn color(&self) -> &'static str {
+        COLORS[self.client % COLORS.len()]
+    }
+
+    /**
+     * Optimized implementations are lighter than the idiomatic ones.
+     */
+    fn opacity(&self) -> f64 {
+        match self.style {
+            Some(Style::Optimized) => 0.5,
+            _ => 1.,
+        }
+    }
+}
+
+struct Chart {
+    title: String,
+    series: Vec<Series>,
+    groups: Vec<Group>,
+}
+
+impl Chart {
+    /**
+     * The series are the ones of `records`, the implementations of a client
+     * next to each other, in the order of `clients`.
+     */
+    fn new(
+        title: &str,
+        clients: &[String],
+        records: &[&Record],
+        label: fn(&Record) -> String,
+    ) -> Self {
+        let mut series: Vec<Series> = Vec::new();
+
+        for record in records {
+            let label = record.series();
+
+            if !series.iter().any(|x| x.label == label) {
+                series.push(Series {
+                    label,
+                    client: clients.iter().position(|x| x == &record.client).unwrap(),
+                    style: record.variant.as_ref().map(|x| x.style),
+                });
+            }
+        }
+
+        // Stable, keeps the order of the implementations of each client.
+        series.sort_by_key(|x| x.client);
+
+        let mut groups: Vec<Group> = Vec::new();
+
+        for record in records {
+            let label = label(record);
+            let index = match groups.iter().position(|x| x.label == label) {
+                Some(index) => index,
+                None => {
+                    groups.push(Group {
+                        label,
+                        bars: series.iter().map(|_| None).collect(),
+                    });
+                    groups.len() - 1
+                }
+            };
+            let label = record.series();
+            let bar = series.iter().position(|x| x.label == label).unwrap();
+
+            groups[index].bars[bar] = Some(Bar {
+                value: record.stats.median,
+                error: record.stats.std_dev,
+            });
+        }
+
+        Self {
+            title: title.to_string(),
+            series,
+            groups,
+        }
+    }
+
+    /**
+     * Expresses each bar as a ratio of the fastest bar of its group.
+     */
+    fn relative(mut self) -> Self {
+        for group in &mut self.groups {
+            let fastest = group
+                .bars
+                .iter()
+                .flatten()
+                .map(|x| x.value)
+                .fold(f64::INFINITY, f64::min);
+
+            for bar in group.bars.iter_mut().flatten() {
+                bar.value /= fastest;
+                bar.error /= fastest;
+            }
+        }
+
+        self
+    }
+
+    fn svg(&self, options: &Options) -> String {
+        let bars = self.groups.iter().flat_map(|x| x.bars.iter().flatten());
+        let (min, max) = bars.fold((f64::INFINITY, 0_f64), |(min, max), bar| {
+            (min.min(bar.value), max.max(bar.value + bar.error))
+        });
+        let axis = Axis::new(min, max, options);
+        let format = |value: f64| {
+            if options.relative {
+                format!("×{}", (value * 1_000.).round() / 1_000.)
+            } else {
+                format_duration(value)
+            }
+        };
+
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let bottom = MARGIN_TOP + plot_height;
+        let y = |value: f64| bottom - axis.position(value) * plot_height;
+
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="{FONT}" font-size="12">"#
+        )
+        .ok();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).ok();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="30" text-anchor="middle" font-size="18">{}</text>"#,
+            MARGIN_LEFT + plot_width / 2.,
+            escape(&self.title)
+        )
+        .ok();
+
+        for tick in &axis.ticks {
+            let y = y(*tick);
+
+            writeln!(
+                svg,
+                r##"<line x1="{MARGIN_LEFT}" y1="{y:.1}" x2="{}" y2="{y:.1}" stroke="#ddd"/>"##,
+                MARGIN_LEFT + plot_width
+            )
+            .ok();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{:.1}" text-anchor="end">{}</text>"#,
+                MARGIN_LEFT - 6.,
+                y + 4.,
+                format(*tick)
+            )
+            .ok();
+        }
+
+        let label = if options.relative {
+            "Relative to fastest"
+        } else {
+            "Duration (median)"
+        };
+        writeln!(
+            svg,
+            r#"<text transform="translate(20 {:.1}) rotate(-90)" text-anchor="middle">{label}</text>"#,
+            MARGIN_TOP + plot_height / 2.
+        )
+        .ok();
+
+        let group_width = plot_width / self.groups.len().max(1) as f64;
+        let bar_width = group_width * 0.8 / self.series.len().max(1) as f64;
+
+        for (i, group) in self.groups.iter().enumerate() {
+            let left = MARGIN_LEFT + i as f64 * group_width + group_width * 0.1;
+
+            for (j, bar) in group.bars.iter().enumerate() {
+                let Some(bar) = bar else {
+                    continue;
+                };
+
+                let x = left + j as f64 * bar_width;
+                let top = y(bar.value);
+                let center = x + bar_width / 2.;
+                let upper = y(bar.value + bar.error);
+                let lower = y(bar.value - bar.error);
+
+                writeln!(
+                    svg,
+                    r#"<rect x="{x:.1}" y="{top:.1}" width="{bar_width:.1}" height="{:.1}" fill="{}" fill-opacity="{}"/>"#,
+                    bottom - top,
+                    self.series[j].color(),
+                    self.series[j].opacity()
+                )
+                .ok();
+                writeln!(
+                    svg,
+                    r#"<path d="M{center:.1} {upper:.1}V{lower:.1}M{:.1} {upper:.1}H{:.1}M{:.1} {lower:.1}H{:.1}" stroke="black"/>"#,
+                    center - bar_width / 4.,
+                    center + bar_width / 4.,
+                    center - bar_width / 4.,
+                    center + bar_width / 4.
+                )
+                .ok();
+            }
+
+            writeln!(
+                svg,
+                r#"<text transform="translate({:.1} {:.1}) rotate(-45)" text-anchor="end">{}</text>"#,
+                left + group_width * 0.4,
+                bottom + 14.,
+                escape(&group.label)
+            )
+            .ok();
+        }
+
+        writeln!(
+            svg,
+            r#"<path d="M{MARGIN_LEFT} {MARGIN_TOP}V{bottom}H{}" fill="none" stroke="black"/>"#,
+            MARGIN_LEFT + plot_width
+        )
+        .ok();
+
+        for (i, series) in self.series.iter().enumerate() {
+            let x = WIDTH - MARGIN_RIGHT + 20.;
+            let y = MARGIN_TOP + i as f64 * 20.;
+
+            writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="12" height="12" fill="{}" fill-opacity="{}"/>"#,
+                series.color(),
+                series.opacity()
+            )
+            .ok();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                x + 18.,
+                y + 11.,
+                escape(&series.label)
+            )
+            .ok();
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+/**
+ * Vertical axis, linear from zero or logarithmic.
+ */
+struct Axis {
+    min: f64,
+    max: f64,
+    log: bool,
+    ticks: Vec<f64>,
+}
+
+impl Axis {
+    fn new(min: f64, max: f64, options: &Options) -> Self {
+        if options.log_scale {
+            let min = 10_f64.powf(min.max(f64::MIN_POSITIVE).log10().floor());
+            let max = 10_f64.powf(max.log10().ceil()).max(min * 10.);
+            let ticks = std::iter::successors(Some(min), |x| Some(x * 10.))
+                .take_while(|x| *x <= max)
+                .collect();
+
+            Self {
+                min,
+                max,
+                log: true,
+                ticks,
+            }
+        } else {
+            let step = nice_step(max / 5.);
+            let max = (max / step).ceil() * step;
+            let ticks = (0..=(max / step).round() as usize)
+                .map(|x| x as f64 * step)
+                .collect();
+
+            Self {
+                min: 0.,
+                max,
+                log: false,
+                ticks,
+            }
+        }
+    }
+
+    /**
+     * Position of `value` on the axis, between 0 and 1.
+     */
+    fn position(&self, value: f64) -> f64 {
+        let position = if self.log {
+            (value.max(self.min).log10() - self.min.log10()) / (self.max.log10() - self.min.log10())
+        } else {
+            (value - self.min) / (self.max - self.min)
+        };
+
+        position.clamp(0., 1.)
+    }
+}
+
+/**
+ * Rounds `step` to 1, 2 or 5 times a power of ten.
+ */
+fn nice_step(step: f64) -> f64 {
+    if step <= 0. {
+        return 1.;
+    }
+
+    let magnitude = 10_f64.powf(step.log10().floor());
+
+    [1., 2., 5., 10.]
+        .into_iter()
+        .map(|x| x * magnitude)
+        .find(|x| *x >= step)
+        .unwrap_or(10. * magnitude)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/**
+ * Renders the chart of all benchmarks in `path` and one chart per operation
+ * next to it (`results/graph` gives `results/graph.svg`,
+ * `results/graph-fetch_all.svg`…), as SVG and PNG.
+ */
+pub fn render(results: &Results, path: &Path, options: &Options) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "graph".to_string());
+
+    if !dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut clients: Vec<String> = Vec::new();
+    let mut operations: Vec<&str> = Vec::new();
+
+    for record in &results.records {
+        if !clients.contains(&record.client) {
+            clients.push(record.client.clone());
+        }
+        if !operations.contains(&record.operation.as_str()) {
+            operations.push(&record.operation);
+        }
+    }
+
+    let mut charts = Vec::new();
+
+    let records = results.records.iter().collect::<Vec<_>>();
+    charts.push((
+        stem.clone(),
+        // The implementations of an operation share the groups of its sizes.
+        Chart::new("Rust SQL client benchmark", &clients, &records, |x| {
+            format!("{}{}", x.operation, &x.name()[x.benchmark().len()..])
+        }),
+    ));
+
+    for operation in operations {
+        let records = results
+            .records
+            .iter()
+            .filter(|x| x.operation == operation)
+            .collect::<Vec<_>>();
+
+        charts.push((
+            format!("{stem}-{operation}"),
+            Chart::new(operation, &clients, &records, |x| {
+                x.name()[x.benchmark().len() + 1..].to_string()
+            }),
+        ));
+    }
+
+    let mut fontdb = resvg::usvg::fontdb::Database::new();
+    fontdb.load_system_fonts();
+    let fontdb = std::sync::Arc::new(fontdb);
+
+    for (name, chart) in charts {
+        let chart = if options.relative {
+            chart.relative()
+        } else {
+            chart
+        };
+        let svg = chart.svg(options);
+
+        std::fs::write(dir.join(format!("{name}.svg")), &svg)?;
+        png(&svg, &dir.join(format!("{name}.png")), fontdb.clone())?;
+    }
+
+    Ok(())
+}
+
+fn png(
+    svg: &str,
+    path: &Path,
+    fontdb: std::sync::Arc<resvg::usvg::fontdb::Database>,
+) -> std::io::Result<()> {
+    let options = resvg::usvg::Options {
+        fontdb,
+        ..Default::default()
+    };
+    let tree = resvg::usvg::Tree::from_str(svg, &options).map_err(std::io::Error::other)?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| std::io::Error::other("invalid chart size"))?;
+
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::default(),
+        &mut pixmap.as_mut(),
+    );
+
+    pixmap.save_png(path).map_err(std::io::Error::other)
+}
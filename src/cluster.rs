@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/**
+ * Settings of the throwaway cluster, pinned so the results don't depend on
+ * the defaults of the installed server. Durability is off: the cluster is
+ * removed after the run, the disk shouldn't be measured.
+ */
+const SETTINGS: &str = "
+listen_addresses = ''
+port = 5432
+max_connections = 200
+shared_buffers = 128MB
+work_mem = 4MB
+maintenance_work_mem = 64MB
+fsync = off
+synchronous_commit = off
+full_page_writes = off
+autovacuum = off
+jit = off
+";
+
+static DSN: OnceLock<String> = OnceLock::new();
+
+/**
+ * Connection string of the cluster started by this process, if any.
+ */
+pub fn dsn() -> Option<String> {
+    DSN.get().cloned()
+}
+
+/**
+ * PostgreSQL cluster created with `initdb` in a temporary directory and
+ * listening on a Unix socket in it. It's stopped and removed when dropped.
+ */
+pub struct Cluster {
+    bindir: PathBuf,
+    dir: PathBuf,
+}
+
+impl Cluster {
+    /**
+     * Starts a cluster, unless `DATABASE_URL` is set.
+     */
+    pub fn start_unless_configured() -> std::io::Result<Option<Self>> {
+        if std::env::var_os("DATABASE_URL").is_some() {
+            Ok(None)
+        } else {
+            Self::start().map(Some)
+        }
+    }
+
+    /**
+     * Creates the cluster with the pinned settings and waits for its start.
+     * Fails as root, `initdb` refuses to run with its privileges.
+     */
+    pub fn start() -> std::io::Result<Self> {
+        if unsafe { libc::geteuid() } == 0 {
+            return Err(std::io::Error::other(
+                "initdb refuses to run as root, set DATABASE_URL to benchmark an existing server",
+            ));
+        }
+
+        let bindir = bindir().ok_or_else(|| {
+            std::io::Error::other(
+                "initdb and pg_ctl not found, install the PostgreSQL server or set DATABASE_URL",
+            )
+        })?;
+        let dir = std::env::temp_dir().join(format!(
+            "elephantry-benchmark-cluster-{}",
+            std::process::id()
+        ));
+
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir(&dir)?;
+
+        // Created before initdb, to remove the directory if a step fails.
+        let cluster = Self { bindir, dir };
+
+        cluster.run(
+            "initdb",
+            &[
+                "--pgdata".as_ref(),
+                cluster.data().as_os_str(),
+                "--username=postgres".as_ref(),
+                "--auth=trust".as_ref(),
+                "--encoding=UTF8".as_ref(),
+                "--locale=C".as_ref(),
+                "--no-sync".as_ref(),
+            ],
+        )?;
+
+        let settings = format!(
+            "{SETTINGS}unix_socket_directories = '{}'\n",
+            cluster.dir.display()
+        );
+        let mut conf = std::fs::OpenOptions::new()
+            .append(true)
+            .open(cluster.data().join("postgresql.conf"))?;
+        std::io::Write::write_all(&mut conf, settings.as_bytes())?;
+
+        cluster.run(
+            "pg_ctl",
+            &[
+                "--pgdata".as_ref(),
+                cluster.data().as_os_str(),
+                "--log".as_ref(),
+                cluster.dir.join("log").as_os_str(),
+                "--wait".as_ref(),
+                "start".as_ref(),
+            ],
+        )?;
+
+        DSN.set(cluster.dsn()).ok();
+
+        Ok(cluster)
+    }
+
+    /**
+     * Connection string of the `postgres` database, through the socket: its
+     * directory is the percent-encoded host, the only form understood by
+     * every client.
+     */
+    pub fn dsn(&self) -> String {
+        let host = self.dir.display().to_string().replace('/', "%2F");
+
+        format!("postgres://postgres@{host}/postgres")
+    }
+
+    fn data(&self) -> PathBuf {
+        self.dir.join("data")
+    }
+
+    /**
+     * Runs one of the server binaries, its output is only printed on failure.
+     */
+    fn run(&self, program: &str, args: &[&std::ffi::OsStr]) -> std::io::Result<()> {
+        let output = std::process::Command::new(self.bindir.join(program))
+            .args(args)
+            .output()?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(std::io::Error::other(format!(
+                "{program} failed ({}): {}{}",
+                output.status,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr),
+            )))
+        }
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        if self.data().join("postmaster.pid").exists() {
+            let stop = self.run(
+                "pg_ctl",
+                &[
+                    "--pgdata".as_ref(),
+                    self.data().as_os_str(),
+                    "--mode=fast".as_ref(),
+                    "--wait".as_ref(),
+                    "stop".as_ref(),
+                ],
+            );
+
+            if let Err(err) = stop {
+                eprintln!("Unable to stop the PostgreSQL cluster: {err}");
+            }
+        }
+
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/**
+ * Directory of `initdb` and `pg_ctl`: in the `PATH`, else the one given by
+ * `pg_config --bindir`, else the newest `/usr/lib/postgresql/<version>/bin`
+ * (debian installs them outside the `PATH`).
+ */
+fn bindir() -> Option<PathBuf> {
+    let is_bindir = |dir: &Path| dir.join("initdb").is_file() && dir.join("pg_ctl").is_file();
+
+    let path = std::env::var_os("PATH").unwrap_or_default();
+
+    if let Some(dir) = std::env::split_paths(&path).find(|x| is_bindir(x)) {
+        return Some(dir);
+    }
+
+    let pg_config = std::process::Command::new("pg_config")
+        .arg("--bindir")
+        .output()
+        .ok()
+        .filter(|x| x.status.success())
+        .map(|x| PathBuf::from(String::from_utf8_lossy(&x.stdout).trim()));
+
+    if let Some(dir) = pg_config.filter(|x| is_bindir(x)) {
+        return Some(dir);
+    }
+
+    std::fs::read_dir("/usr/lib/postgresql")
+        .ok()?
+        .filter_map(|x| {
+            let entry = x.ok()?;
+            let version = entry.file_name().to_str()?.parse::<u32>().ok()?;
+
+            Some((version, entry.path().join("bin")))
+        })
+        .filter(|(_, dir)| is_bindir(dir))
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, dir)| dir)
+}
@@ -0,0 +1,200 @@
+use crate::results::{Record, Results};
+use crate::stats;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Improved,
+    Regressed,
+    /**
+     * The change isn't significant or stays under the threshold.
+     */
+    Unchanged,
+}
+
+/**
+ * Change of one benchmark between the baseline and the current results.
+ */
+#[derive(Clone, Debug)]
+pub struct Change {
+    pub client: String,
+    /**
+     * Benchmark name, see [`Record::name`].
+     */
+    pub name: String,
+    /**
+     * Median duration of the baseline, in nanoseconds.
+     */
+    pub baseline: f64,
+    /**
+     * Median duration of the current results, in nanoseconds.
+     */
+    pub current: f64,
+    /**
+     * Relative change of the median, `0.1` is 10% slower.
+     */
+    pub change: f64,
+    pub p_value: f64,
+    pub verdict: Verdict,
+}
+
+pub struct Comparison {
+    pub changes: Vec<Change>,
+}
+
+impl Comparison {
+    /**
+     * Compares the benchmarks present in both results. A change is an
+     * improvement or a regression when the samples differ significantly
+     * (Mann-Whitney U test p-value under `significance_level`) and the
+     * median moves by more than `threshold` (`0.05` for 5%).
+     */
+    pub fn new(
+        baseline: &Results,
+        current: &Results,
+        threshold: f64,
+        significance_level: f64,
+    ) -> Self {
+        let changes = current
+            .records
+            .iter()
+            .filter_map(|record| {
+                let old = baseline.records.iter().find(|x| same(x, record))?;
+                let p_value = stats::mann_whitney(&old.samples, &record.samples);
+                let change = record.stats.median / old.stats.median - 1.;
+
+                let verdict = if p_value >= significance_level || change.abs() < threshold {
+                    Verdict::Unchanged
+                } else if change > 0. {
+                    Verdict::Regressed
+                } else {
+                    Verdict::Improved
+                };
+
+                Some(Change {
+                    client: record.client.clone(),
+                    name: record.name(),
+                    baseline: old.stats.median,
+                    current: record.stats.median,
+                    change,
+                    p_value,
+                    verdict,
+                })
+            })
+            .collect();
+
+        Self { changes }
+    }
+
+    pub fn regressions(&self) -> usize {
+        self.changes
+            .iter()
+            .filter(|x| x.verdict == Verdict::Regressed)
+            .count()
+    }
+
+    pub fn report(&self, name: &str) {
+        println!("\ncomparison with the {name} baseline");
+        println!(
+            "{:<16}{:<24}{:>14}{:>14}{:>10}{:>10}",
+            "client", "benchmark", "baseline", "current", "change", "p-value"
+        );
+
+        for change in &self.changes {
+            let verdict = match change.verdict {
+                Verdict::Improved => "improved",
+                Verdict::Regressed => "regressed",
+                Verdict::Unchanged => "",
+            };
+
+            let line = format!(
+                "{:<16}{:<24}{:>14}{:>14}{:>10}{:>10.3}  {verdict}",
+                change.client,
+                change.name,
+                stats::format_duration(change.baseline),
+                stats::format_duration(change.current),
+                format!("{:+.2}%", change.change * 100.),
+                change.p_value
+            );
+
+            println!("{}", line.trim_end());
+        }
+    }
+}
+
+fn same(a: &Record, b: &Record) -> bool {
+    a.client == b.client && a.name() == b.name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, Verdict};
+    use crate::results::{Record, Results};
+    use crate::stats::Stats;
+
+    fn results(operation: &str, samples: Vec<f64>) -> Results {
+        Results::new(vec![Record {
+            client: "postgres".to_string(),
+            operation: operation.to_string(),
+            size: 100,
+            stats: Stats::new(&samples, 0.95, 100),
+            samples,
+            allocations: None,
+            concurrency: None,
+            variant: None,
+        }])
+    }
+
+    fn samples(median: f64) -> Vec<f64> {
+        (0..=50).map(|x| median - 25. + x as f64).collect()
+    }
+
+    #[test]
+    fn regressed() {
+        let baseline = results("fetch_all", samples(1_000.));
+        let current = results("fetch_all", samples(1_200.));
+        let comparison = Comparison::new(&baseline, &current, 0.05, 0.05);
+
+        assert_eq!(comparison.changes.len(), 1);
+        assert_eq!(comparison.changes[0].name, "fetch_all/100");
+        assert!((comparison.changes[0].change - 0.2).abs() < 1e-9);
+        assert!(comparison.changes[0].p_value < 1e-6);
+        assert_eq!(comparison.changes[0].verdict, Verdict::Regressed);
+        assert_eq!(comparison.regressions(), 1);
+
+        let comparison = Comparison::new(&current, &baseline, 0.05, 0.05);
+
+        assert_eq!(comparison.changes[0].verdict, Verdict::Improved);
+        assert_eq!(comparison.regressions(), 0);
+    }
+
+    #[test]
+    fn unchanged() {
+        let baseline = results("fetch_all", samples(1_000.));
+
+        // Significant, but under the threshold.
+        let current = results("fetch_all", samples(1_030.));
+        let comparison = Comparison::new(&baseline, &current, 0.05, 0.05);
+
+        assert!(comparison.changes[0].p_value < 0.05);
+        assert_eq!(comparison.changes[0].verdict, Verdict::Unchanged);
+
+        // Over the threshold, but not significant.
+        let current = results("fetch_all", vec![900., 1_100., 1_300.]);
+        let comparison = Comparison::new(&baseline, &current, 0.05, 0.05);
+
+        assert!(comparison.changes[0].p_value >= 0.05);
+        assert_eq!(comparison.changes[0].verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn missing() {
+        let baseline = results("fetch_all", samples(1_000.));
+        let current = results("fetch_first", samples(1_000.));
+
+        assert!(
+            Comparison::new(&baseline, &current, 0.05, 0.05)
+                .changes
+                .is_empty()
+        );
+    }
+}
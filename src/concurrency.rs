@@ -0,0 +1,191 @@
+use crate::{AsyncClient, Client};
+use std::time::{Duration, Instant};
+
+/**
+ * Maximum number of latencies kept per benchmark, evenly picked among all
+ * the calls of all the workers.
+ */
+const MAX_SAMPLES: usize = 5_000;
+
+/**
+ * Operations run by the workers of a concurrency benchmark.
+ */
+#[derive(Clone, Copy, Debug)]
+pub enum Operation {
+    FetchAll,
+    FetchFirst,
+    InsertUser,
+    OneRelation,
+}
+
+impl Operation {
+    pub const ALL: [Self; 4] = [
+        Self::FetchAll,
+        Self::FetchFirst,
+        Self::InsertUser,
+        Self::OneRelation,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FetchAll => "concurrent_fetch_all",
+            Self::FetchFirst => "concurrent_fetch_first",
+            Self::InsertUser => "concurrent_insert_user",
+            Self::OneRelation => "concurrent_one_relation",
+        }
+    }
+
+    fn call<C: Client>(&self, client: &mut C) -> Result<(), C::Error> {
+        match self {
+            Self::FetchAll => client.fetch_all().map(|_| ()),
+            Self::FetchFirst => client.fetch_first().map(|_| ()),
+            Self::InsertUser => client.insert_user(),
+            Self::OneRelation => client.one_relation().map(|_| ()),
+        }
+    }
+
+    async fn call_async<C: AsyncClient>(&self, client: &mut C) -> Result<(), C::Error> {
+        match self {
+            Self::FetchAll => client.fetch_all().await.map(|_| ()),
+            Self::FetchFirst => client.fetch_first().await.map(|_| ()),
+            Self::InsertUser => client.insert_user().await,
+            Self::OneRelation => client.one_relation().await.map(|_| ()),
+        }
+    }
+}
+
+/**
+ * Calls of all the workers during the measurement.
+ */
+pub struct Measure {
+    /**
+     * Latency of the calls, in nanoseconds.
+     */
+    pub samples: Vec<f64>,
+    /**
+     * Calls per second, all workers together.
+     */
+    pub throughput: f64,
+}
+
+impl Measure {
+    fn new(latencies: Vec<Vec<f64>>, elapsed: Duration) -> Self {
+        let calls = latencies.iter().map(Vec::len).sum::<usize>();
+        let latencies = latencies.concat();
+
+        let samples = if latencies.len() > MAX_SAMPLES {
+            (0..MAX_SAMPLES)
+                .map(|i| latencies[i * latencies.len() / MAX_SAMPLES])
+                .collect()
+        } else {
+            latencies
+        };
+
+        Self {
+            samples,
+            throughput: calls as f64 / elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/**
+ * Runs `operation` in a loop from one thread per worker: during the warm up
+ * time, then during the measurement time, recording the latency of each
+ * call.
+ */
+pub fn run<C: Client + Send>(
+    workers: Vec<C>,
+    operation: Operation,
+    warm_up_time: Duration,
+    measurement_time: Duration,
+) -> Result<Measure, C::Error>
+where
+    C::Error: Send,
+{
+    let measure = Instant::now() + warm_up_time;
+    let end = measure + measurement_time;
+
+    let latencies = std::thread::scope(|scope| {
+        let handles = workers
+            .into_iter()
+            .map(|mut worker| {
+                scope.spawn(move || {
+                    let mut latencies = Vec::new();
+
+                    loop {
+                        let call = Instant::now();
+
+                        if call >= end {
+                            break;
+                        }
+
+                        operation.call(&mut worker)?;
+
+                        if call >= measure {
+                            latencies.push(call.elapsed().as_nanos() as f64);
+                        }
+                    }
+
+                    Ok(latencies)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|x| x.join().unwrap())
+            .collect::<Result<Vec<_>, C::Error>>()
+    })?;
+
+    Ok(Measure::new(latencies, measure.elapsed()))
+}
+
+/**
+ * Same as [`run`] with one task per worker, spawned on the current tokio
+ * runtime.
+ */
+pub async fn run_async<C: AsyncClient + 'static>(
+    workers: Vec<C>,
+    operation: Operation,
+    warm_up_time: Duration,
+    measurement_time: Duration,
+) -> Result<Measure, C::Error>
+where
+    C::Error: 'static,
+{
+    let measure = Instant::now() + warm_up_time;
+    let end = measure + measurement_time;
+
+    let handles = workers
+        .into_iter()
+        .map(|mut worker| {
+            tokio::spawn(async move {
+                let mut latencies = Vec::new();
+
+                loop {
+                    let call = Instant::now();
+
+                    if call >= end {
+                        break;
+                    }
+
+                    operation.call_async(&mut worker).await?;
+
+                    if call >= measure {
+                        latencies.push(call.elapsed().as_nanos() as f64);
+                    }
+                }
+
+                Ok(latencies)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut latencies = Vec::with_capacity(handles.len());
+
+    for handle in handles {
+        latencies.push(handle.await.unwrap()?);
+    }
+
+    Ok(Measure::new(latencies, measure.elapsed()))
+}
@@ -0,0 +1,450 @@
+use crate::UUID;
+
+/**
+ * Seed of the generated users and posts, loaded with `COPY` in the template
+ * databases: every client, on every run, gets the same rows (with the same
+ * fastrand version).
+ */
+pub const SEED: u64 = 0x5eed_e1e9_4a47_0001;
+
+/**
+ * Statement loading [`users_text`].
+ */
+pub const USERS_COPY: &str = "COPY users (id, name, hair_color, created_at) FROM STDIN";
+
+/**
+ * Statement loading [`posts_text`].
+ */
+pub const POSTS_COPY: &str = "COPY posts (id, title, content, author) FROM STDIN";
+
+/**
+ * Ratio of users without hair color.
+ */
+const NULL_HAIR_COLOR: f64 = 0.2;
+
+/**
+ * Creation dates are spread from 2020-01-01 to 2025-01-01, in microseconds
+ * since the Unix epoch.
+ */
+const CREATED_FROM: i64 = 1_577_836_800_000_000;
+const CREATED_TO: i64 = 1_735_689_600_000_000;
+
+const FIRST_NAMES: [&str; 24] = [
+    "Al",
+    "Ann",
+    "Bo",
+    "Chloé",
+    "Dmitri",
+    "Eve",
+    "Fatima",
+    "Guillaume",
+    "Hiroshi",
+    "Ines",
+    "Jo",
+    "Kwame",
+    "Lea",
+    "Maximilian",
+    "Nour",
+    "Olu",
+    "Priyanka",
+    "Quinn",
+    "Rosalind",
+    "Sven",
+    "Tuğba",
+    "Ulrich",
+    "Valentina",
+    "Xi",
+];
+
+const LAST_NAMES: [&str; 24] = [
+    "Li",
+    "Ng",
+    "Diaz",
+    "Dupont",
+    "Okafor",
+    "Müller",
+    "Nakamura",
+    "Kowalczyk",
+    "Andersson",
+    "Fitzgerald",
+    "Oyelaran",
+    "Papadopoulos",
+    "Rossi",
+    "Sato",
+    "Thompson",
+    "Van der Berg",
+    "Wójcik",
+    "Yilmaz",
+    "Zhang",
+    "Abernathy-Lowe",
+    "Bianchi",
+    "Castellanos",
+    "Deschamps",
+    "Ó Súilleabháin",
+];
+
+const HAIR_COLORS: [&str; 8] = [
+    "black",
+    "brown",
+    "dark brown",
+    "blond",
+    "strawberry blond",
+    "red",
+    "grey",
+    "white",
+];
+
+const WORDS: [&str; 32] = [
+    "postgres",
+    "rust",
+    "query",
+    "index",
+    "table",
+    "row",
+    "benchmark",
+    "client",
+    "async",
+    "the",
+    "a",
+    "of",
+    "with",
+    "fast",
+    "slow",
+    "why",
+    "how",
+    "plan",
+    "cache",
+    "server",
+    "network",
+    "copy",
+    "binary",
+    "text",
+    "pool",
+    "connection",
+    "latency",
+    "throughput",
+    "notes",
+    "on",
+    "and",
+    "tuning",
+];
+
+pub struct User {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub hair_color: Option<String>,
+    /**
+     * Microseconds since the Unix epoch, see [`timestamp`].
+     */
+    pub created_at: i64,
+}
+
+pub struct Post {
+    pub id: uuid::Uuid,
+    pub title: String,
+    pub content: String,
+    pub author: uuid::Uuid,
+}
+
+/**
+ * Description of the rows of a template database, generated by
+ * [`Dataset::generate`].
+ */
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Dataset {
+    users: usize,
+    posts: usize,
+    keyed: bool,
+}
+
+impl Dataset {
+    /**
+     * `n` users with `posts` posts each, plus the user identified by
+     * [`UUID`] with as many posts, created by `setup(n)`.
+     */
+    pub fn new(n: usize, posts: usize) -> Self {
+        Self {
+            users: n,
+            posts,
+            keyed: false,
+        }
+    }
+
+    /**
+     * `n` users without posts identified by `Uuid::from_u128(1..=n)`, plus
+     * the user identified by [`UUID`], created by `setup_keyed(n)`.
+     */
+    pub fn keyed(n: usize) -> Self {
+        Self {
+            users: n,
+            posts: 0,
+            keyed: true,
+        }
+    }
+
+    /**
+     * Generates the users, the one identified by [`UUID`] last, and their
+     * posts. The first users of a larger dataset are the same.
+     */
+    pub fn generate(&self) -> (Vec<User>, Vec<Post>) {
+        let mut rng = fastrand::Rng::with_seed(SEED);
+        let mut users = Vec::with_capacity(crate::rows(self.users));
+        let mut posts = Vec::with_capacity(crate::rows(self.users) * self.posts);
+
+        for i in 0..self.users {
+            let mut user = user(&mut rng);
+
+            if self.keyed {
+                user.id = uuid::Uuid::from_u128(i as u128 + 1);
+            }
+
+            for _ in 0..self.posts {
+                posts.push(post(&mut rng, user.id));
+            }
+
+            users.push(user);
+        }
+
+        let user = User {
+            id: UUID,
+            name: "Sanpi".to_string(),
+            hair_color: Some("Blue".to_string()),
+            created_at: CREATED_FROM,
+        };
+
+        for _ in 0..self.posts {
+            posts.push(post(&mut rng, user.id));
+        }
+
+        users.push(user);
+
+        (users, posts)
+    }
+}
+
+fn user(rng: &mut fastrand::Rng) -> User {
+    let first = FIRST_NAMES[rng.usize(..FIRST_NAMES.len())];
+    let last = LAST_NAMES[rng.usize(..LAST_NAMES.len())];
+    let name = if rng.f64() < 0.1 {
+        format!("{first} {}. {last}", rng.uppercase())
+    } else {
+        format!("{first} {last}")
+    };
+    let hair_color = if rng.f64() < NULL_HAIR_COLOR {
+        None
+    } else {
+        Some(HAIR_COLORS[rng.usize(..HAIR_COLORS.len())].to_string())
+    };
+
+    User {
+        id: id(rng),
+        name,
+        hair_color,
+        created_at: rng.i64(CREATED_FROM..CREATED_TO),
+    }
+}
+
+fn post(rng: &mut fastrand::Rng, author: uuid::Uuid) -> Post {
+    let mut title = words(rng, 3..=10);
+
+    title[..1].make_ascii_uppercase();
+
+    let content = (0..rng.usize(1..=3))
+        .map(|_| {
+            let mut sentence = words(rng, 5..=15);
+
+            sentence[..1].make_ascii_uppercase();
+            sentence.push('.');
+            sentence
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Post {
+        id: id(rng),
+        title,
+        content,
+        author,
+    }
+}
+
+/**
+ * Version 4 UUID, from the generator instead of the OS.
+ */
+fn id(rng: &mut fastrand::Rng) -> uuid::Uuid {
+    uuid::Builder::from_random_bytes(rng.u128(..).to_le_bytes()).into_uuid()
+}
+
+fn words(rng: &mut fastrand::Rng, count: std::ops::RangeInclusive<usize>) -> String {
+    (0..rng.usize(count))
+        .map(|_| WORDS[rng.usize(..WORDS.len())])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/**
+ * `users` in the text format of [`USERS_COPY`].
+ */
+pub fn users_text(users: &[User]) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for user in users {
+        let row = format!(
+            "{}\t{}\t{}\t{}\n",
+            user.id,
+            escape(&user.name),
+            user.hair_color.as_deref().map_or("\\N".to_string(), escape),
+            timestamp(user.created_at),
+        );
+
+        data.extend_from_slice(row.as_bytes());
+    }
+
+    data
+}
+
+/**
+ * `posts` in the text format of [`POSTS_COPY`].
+ */
+pub fn posts_text(posts: &[Post]) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for post in posts {
+        let row = format!(
+            "{}\t{}\t{}\t{}\n",
+            post.id,
+            escape(&post.title),
+            escape(&post.content),
+            post.author,
+        );
+
+        data.extend_from_slice(row.as_bytes());
+    }
+
+    data
+}
+
+/**
+ * Escapes a value of the text format, no generated value needs it today.
+ */
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/**
+ * `timestamp` literal of `micros` microseconds since the Unix epoch, UTC.
+ */
+pub fn timestamp(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000);
+    let days = secs.div_euclid(86_400);
+    let time = secs.rem_euclid(86_400);
+
+    // Civil date of a number of days since the epoch (Howard Hinnant's
+    // `civil_from_days`).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:06}",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60,
+        micros.rem_euclid(1_000_000),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dataset, posts_text, timestamp, users_text};
+
+    #[test]
+    fn epoch() {
+        assert_eq!(timestamp(0), "1970-01-01 00:00:00.000000");
+        assert_eq!(timestamp(-1), "1969-12-31 23:59:59.999999");
+        assert_eq!(timestamp(1_234_567), "1970-01-01 00:00:01.234567");
+    }
+
+    #[test]
+    fn leap_days() {
+        assert_eq!(
+            timestamp(1_582_934_400_000_000),
+            "2020-02-29 00:00:00.000000"
+        );
+        // Divisible by 400.
+        assert_eq!(timestamp(951_782_400_000_000), "2000-02-29 00:00:00.000000");
+        // Divisible by 100, not a leap year.
+        assert_eq!(
+            timestamp(-2_203_891_200_000_000),
+            "1900-03-01 00:00:00.000000"
+        );
+    }
+
+    #[test]
+    fn year_ends() {
+        assert_eq!(timestamp(946_684_799_000_000), "1999-12-31 23:59:59.000000");
+        assert_eq!(timestamp(super::CREATED_FROM), "2020-01-01 00:00:00.000000");
+        assert_eq!(
+            timestamp(super::CREATED_TO - 1),
+            "2024-12-31 23:59:59.999999"
+        );
+    }
+
+    #[test]
+    fn deterministic() {
+        let (users, posts) = Dataset::new(100, 3).generate();
+        let (again, again_posts) = Dataset::new(100, 3).generate();
+
+        assert_eq!(users_text(&users), users_text(&again));
+        assert_eq!(posts_text(&posts), posts_text(&again_posts));
+
+        assert_eq!(users.len(), crate::rows(100));
+        assert_eq!(posts.len(), crate::rows(100) * 3);
+        assert_eq!(users.last().unwrap().id, crate::UUID);
+    }
+
+    #[test]
+    fn prefix() {
+        let (small, small_posts) = Dataset::new(10, 3).generate();
+        let (large, large_posts) = Dataset::new(100, 3).generate();
+
+        assert_eq!(users_text(&small[..10]), users_text(&large[..10]));
+        assert_eq!(
+            posts_text(&small_posts[..30]),
+            posts_text(&large_posts[..30])
+        );
+    }
+
+    #[test]
+    fn keyed() {
+        let (users, posts) = Dataset::keyed(3).generate();
+        let ids = users.iter().map(|x| x.id.as_u128()).collect::<Vec<_>>();
+
+        assert_eq!(ids, [1, 2, 3, crate::UUID.as_u128()]);
+        assert!(posts.is_empty());
+    }
+
+    #[test]
+    fn text() {
+        let (users, _) = Dataset::new(1_000, 0).generate();
+        let text = String::from_utf8(users_text(&users)).unwrap();
+
+        assert_eq!(text.lines().count(), users.len());
+        assert!(text.lines().all(|x| x.split('\t').count() == 4));
+        // A hair color is missing for 20% of the users.
+        assert!(text.contains("\t\\N\t"));
+        assert!(text.ends_with(
+            "85e11126-a41d-4dce-98f8-731a87685d2c\tSanpi\tBlue\t2020-01-01 00:00:00.000000\n"
+        ));
+    }
+}
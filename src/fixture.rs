@@ -0,0 +1,461 @@
+use crate::dataset::Dataset;
+use crate::{AsyncClient, Client};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+/**
+ * Template database built by this process for each setup script and
+ * dataset.
+ */
+static TEMPLATES: Mutex<BTreeMap<(String, Option<Dataset>), String>> = Mutex::new(BTreeMap::new());
+
+/**
+ * Database created by this process, on the server of [`crate::dsn`].
+ */
+struct Database {
+    name: String,
+}
+
+impl Database {
+    /**
+     * Picks a new name, `<prefix>_<pid>_<random>`, unique to this process
+     * and run.
+     */
+    fn new(prefix: &str) -> Self {
+        Self {
+            name: format!("{prefix}_{}_{:08x}", std::process::id(), fastrand::u32(..)),
+        }
+    }
+
+    /**
+     * Connection string of the benchmark database with this database
+     * instead.
+     */
+    fn dsn(&self) -> String {
+        with_dbname(&crate::dsn(), &self.name)
+    }
+
+    fn create_query(&self, template: Option<&str>) -> String {
+        match template {
+            Some(template) => format!("create database {} template {template}", self.name),
+            None => format!("create database {}", self.name),
+        }
+    }
+}
+
+/**
+ * `dsn` connecting to the `dbname` database instead, in both forms of the
+ * libpq connection strings: an URI (`postgres://host/db?param=value`) or
+ * `key=value` pairs (`host=/tmp dbname=db`).
+ */
+fn with_dbname(dsn: &str, dbname: &str) -> String {
+    match dsn.split_once("://") {
+        Some((scheme @ ("postgres" | "postgresql"), rest)) => {
+            let (base, query) = rest.split_once('?').unwrap_or((rest, ""));
+            let authority = base.split('/').next().unwrap_or_default();
+            // A `dbname` parameter overrides the path.
+            let params = query
+                .split('&')
+                .filter(|x| !x.is_empty() && !x.starts_with("dbname="))
+                .collect::<Vec<_>>();
+
+            if params.is_empty() {
+                format!("{scheme}://{authority}/{dbname}")
+            } else {
+                format!("{scheme}://{authority}/{dbname}?{}", params.join("&"))
+            }
+        }
+        _ => {
+            let mut pairs = Vec::new();
+            let mut rest = dsn.trim_start();
+
+            while !rest.is_empty() {
+                let (pair, next) = rest.split_at(pair_len(rest));
+                let key = pair.split('=').next().unwrap_or_default().trim();
+
+                if key != "dbname" {
+                    pairs.push(pair);
+                }
+
+                rest = next.trim_start();
+            }
+
+            let dbname = format!("dbname={dbname}");
+            pairs.push(&dbname);
+
+            pairs.join(" ")
+        }
+    }
+}
+
+/**
+ * Length of the `key = value` pair at the start of `s`, the value is single
+ * quoted or ends at the first whitespace, with backslash escapes.
+ */
+fn pair_len(s: &str) -> usize {
+    let mut chars = s.char_indices().peekable();
+
+    while chars
+        .next_if(|(_, c)| *c != '=' && !c.is_whitespace())
+        .is_some()
+    {}
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    chars.next_if(|(_, c)| *c == '=');
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+    let quoted = chars.next_if(|(_, c)| *c == '\'').is_some();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\'' if quoted => return i + 1,
+            c if !quoted && c.is_whitespace() => return i,
+            _ => (),
+        }
+    }
+
+    s.len()
+}
+
+/**
+ * Query dropping the `name` database, even if a connection to it is still
+ * open.
+ */
+fn drop_query(name: &str) -> String {
+    format!("drop database if exists {name} with (force)")
+}
+
+fn drop_database<C: Client>(name: &str) {
+    let result = C::create(&crate::dsn()).and_then(|mut conn| conn.exec(&drop_query(name)));
+
+    if let Err(err) = result {
+        eprintln!("Unable to drop the {name} database: {err:?}");
+    }
+}
+
+async fn drop_database_async<C: AsyncClient>(name: &str) {
+    let result = async {
+        let mut conn = C::create(&crate::dsn()).await?;
+
+        conn.exec(&drop_query(name)).await
+    }
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Unable to drop the {name} database: {err:?}");
+    }
+}
+
+/**
+ * Runs `f` to completion from a `Drop`, on the current runtime, which must be
+ * a multi-threaded one.
+ */
+fn block_on<F: Future>(f: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(f))
+}
+
+/**
+ * Template database built by `script`, then loaded with the rows of
+ * `dataset`, at the first call for them. The database is dropped if a step
+ * fails.
+ */
+fn template<C: Client>(script: &str, dataset: Option<Dataset>) -> Result<String, C::Error> {
+    let key = (script.to_string(), dataset);
+
+    if let Some(name) = TEMPLATES.lock().unwrap().get(&key) {
+        return Ok(name.clone());
+    }
+
+    let database = Database::new("bench_template");
+
+    C::create(&crate::dsn())?.exec(&database.create_query(None))?;
+
+    // The connection is closed at the end of the statement, a template
+    // can't be copied while someone is connected to it.
+    let built = C::create(&database.dsn()).and_then(|mut conn| {
+        conn.exec(script)?;
+
+        if let Some(dataset) = dataset {
+            let (users, posts) = dataset.generate();
+
+            conn.load(&users, &posts)?;
+        }
+
+        Ok(())
+    });
+
+    if let Err(err) = built {
+        drop_database::<C>(&database.name);
+
+        return Err(err);
+    }
+
+    TEMPLATES.lock().unwrap().insert(key, database.name.clone());
+
+    Ok(database.name)
+}
+
+async fn template_async<C: AsyncClient>(
+    script: &str,
+    dataset: Option<Dataset>,
+) -> Result<String, C::Error> {
+    let key = (script.to_string(), dataset);
+    let cached = TEMPLATES.lock().unwrap().get(&key).cloned();
+
+    if let Some(name) = cached {
+        return Ok(name);
+    }
+
+    let database = Database::new("bench_template");
+
+    C::create(&crate::dsn())
+        .await?
+        .exec(&database.create_query(None))
+        .await?;
+
+    let built = async {
+        let mut conn = C::create(&database.dsn()).await?;
+
+        conn.exec(script).await?;
+
+        if let Some(dataset) = dataset {
+            let (users, posts) = dataset.generate();
+
+            conn.load(&users, &posts).await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = built {
+        drop_database_async::<C>(&database.name).await;
+
+        return Err(err);
+    }
+
+    TEMPLATES.lock().unwrap().insert(key, database.name.clone());
+
+    Ok(database.name)
+}
+
+/**
+ * Connection of a benchmark to its own database, created by the `setup`
+ * functions of [`Client`]: a copy of the template database built once by
+ * the setup script and dataset for all the benchmarks using the same ones.
+ * It derefs to the client, and drops the database when dropped, including
+ * when the benchmark panics.
+ */
+pub struct Fixture<C: Client> {
+    /**
+     * Closed before dropping the database, which isn't possible while
+     * connected to it.
+     */
+    client: Option<C>,
+    database: Database,
+}
+
+impl<C: Client> Fixture<C> {
+    /**
+     * Connects to a new copy of the template database built by `script`
+     * and loaded with `dataset`.
+     */
+    pub fn create(script: &str, dataset: Option<Dataset>) -> Result<Self, C::Error> {
+        env_logger::try_init().ok();
+
+        let template = template::<C>(script, dataset)?;
+        let mut fixture = Self {
+            client: None,
+            database: Database::new("bench"),
+        };
+
+        C::create(&crate::dsn())?.exec(&fixture.database.create_query(Some(&template)))?;
+        fixture.client = Some(C::create(&fixture.database.dsn())?);
+
+        Ok(fixture)
+    }
+
+    /**
+     * Connection of a concurrency benchmark worker, see [`Client::worker`].
+     */
+    pub fn worker(&self) -> Result<C, C::Error> {
+        self.client.as_ref().unwrap().worker(&self.database.dsn())
+    }
+}
+
+impl<C: Client> std::ops::Deref for Fixture<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl<C: Client> std::ops::DerefMut for Fixture<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl<C: Client> Drop for Fixture<C> {
+    fn drop(&mut self) {
+        self.client.take();
+
+        drop_database::<C>(&self.database.name);
+    }
+}
+
+/**
+ * Same as [`Fixture`] for an async client. The database is dropped by
+ * blocking the current thread, which must belong to a multi-threaded
+ * runtime.
+ */
+pub struct AsyncFixture<C: AsyncClient> {
+    client: Option<C>,
+    database: Database,
+}
+
+impl<C: AsyncClient> AsyncFixture<C> {
+    /**
+     * Same as [`Fixture::create`].
+     */
+    pub async fn create(script: &str, dataset: Option<Dataset>) -> Result<Self, C::Error> {
+        env_logger::try_init().ok();
+
+        let template = template_async::<C>(script, dataset).await?;
+        let mut fixture = Self {
+            client: None,
+            database: Database::new("bench"),
+        };
+
+        C::create(&crate::dsn())
+            .await?
+            .exec(&fixture.database.create_query(Some(&template)))
+            .await?;
+        fixture.client = Some(C::create(&fixture.database.dsn()).await?);
+
+        Ok(fixture)
+    }
+
+    /**
+     * Connection of a concurrency benchmark task, see [`AsyncClient::worker`].
+     */
+    pub async fn worker(&self) -> Result<C, C::Error> {
+        self.client
+            .as_ref()
+            .unwrap()
+            .worker(&self.database.dsn())
+            .await
+    }
+}
+
+impl<C: AsyncClient> std::ops::Deref for AsyncFixture<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl<C: AsyncClient> std::ops::DerefMut for AsyncFixture<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl<C: AsyncClient> Drop for AsyncFixture<C> {
+    fn drop(&mut self) {
+        self.client.take();
+
+        block_on(drop_database_async::<C>(&self.database.name));
+    }
+}
+
+/**
+ * Guard of the template databases built by the fixtures, all dropped with
+ * it. Held by the `main` of the bench binaries, before the harness exits.
+ */
+pub struct Templates<C: Client>(PhantomData<C>);
+
+impl<C: Client> Default for Templates<C> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Client> Drop for Templates<C> {
+    fn drop(&mut self) {
+        let templates = std::mem::take(&mut *TEMPLATES.lock().unwrap());
+
+        for name in templates.values() {
+            drop_database::<C>(name);
+        }
+    }
+}
+
+/**
+ * Same as [`Templates`] for an async client, see [`AsyncFixture`].
+ */
+pub struct AsyncTemplates<C: AsyncClient>(PhantomData<C>);
+
+impl<C: AsyncClient> Default for AsyncTemplates<C> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: AsyncClient> Drop for AsyncTemplates<C> {
+    fn drop(&mut self) {
+        let templates = std::mem::take(&mut *TEMPLATES.lock().unwrap());
+
+        for name in templates.values() {
+            block_on(drop_database_async::<C>(name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_dbname;
+
+    #[test]
+    fn url() {
+        assert_eq!(
+            with_dbname("postgres://user@localhost:5432/postgres", "bench"),
+            "postgres://user@localhost:5432/bench"
+        );
+        assert_eq!(
+            with_dbname("postgresql://localhost?sslmode=disable", "bench"),
+            "postgresql://localhost/bench?sslmode=disable"
+        );
+        assert_eq!(
+            with_dbname("postgres://postgres@%2Ftmp%2Fcluster/postgres", "bench"),
+            "postgres://postgres@%2Ftmp%2Fcluster/bench"
+        );
+        assert_eq!(
+            with_dbname("postgres:///postgres?host=/tmp&dbname=postgres", "bench"),
+            "postgres:///bench?host=/tmp"
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(
+            with_dbname("host=/tmp dbname=postgres", "bench"),
+            "host=/tmp dbname=bench"
+        );
+        assert_eq!(with_dbname("host=/tmp", "bench"), "host=/tmp dbname=bench");
+        assert_eq!(with_dbname("", "bench"), "dbname=bench");
+        assert_eq!(
+            with_dbname(r"dbname = 'my db' user='o\'neil' port=5432", "bench"),
+            r"user='o\'neil' port=5432 dbname=bench"
+        );
+        assert_eq!(
+            with_dbname(r"password=a\ b dbname=postgres", "bench"),
+            r"password=a\ b dbname=bench"
+        );
+    }
+}
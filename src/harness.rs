@@ -0,0 +1,1203 @@
+use crate::allocator::{self, Allocations, Counter};
+use crate::cluster::Cluster;
+use crate::concurrency::{self, Measure, Operation};
+use crate::results::{Concurrency, Record, Results, Variant};
+use crate::stats::{self, Stats};
+use crate::{AsyncClient, Client, Implementation};
+use std::panic::AssertUnwindSafe;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/**
+ * Benchmarks configuration, read from the command line arguments of the
+ * bench binary (`cargo bench -- --sample-size 50`).
+ */
+#[derive(Clone, Debug, clap::Parser)]
+pub struct Config {
+    /// Only run benchmarks whose name contains this string.
+    pub filter: Option<String>,
+    /// Only run these operations (all by default).
+    #[arg(long, value_delimiter = ',')]
+    pub operations: Vec<String>,
+    /// Time spent running the routine before measuring it, in seconds.
+    #[arg(long, default_value = "1", value_parser = parse_duration)]
+    pub warm_up_time: Duration,
+    /// Target time of the measurement, in seconds.
+    #[arg(long, default_value = "3", value_parser = parse_duration)]
+    pub measurement_time: Duration,
+    /// Number of samples collected per benchmark.
+    #[arg(long, default_value_t = 100, value_parser = parse_sample_size)]
+    pub sample_size: usize,
+    /// Confidence level of the reported intervals.
+    #[arg(long, default_value_t = 0.95, value_parser = parse_confidence_level)]
+    pub confidence_level: f64,
+    /// Dataset sizes (number of generated users) each operation runs on.
+    #[arg(long, value_delimiter = ',', default_value = "1,100,10000", value_parser = parse_size)]
+    pub sizes: Vec<usize>,
+    /// Dataset sizes of the benchmarks reading large result sets, like the
+    /// streaming one.
+    #[arg(long, value_delimiter = ',', default_value = "1000000", value_parser = parse_size)]
+    pub large_sizes: Vec<usize>,
+    /// Sizes in bytes of the payloads of the large values benchmarks, from
+    /// 1 KiB to 64 MiB by default.
+    #[arg(long, value_delimiter = ',', default_value = "1024,1048576,67108864")]
+    pub payload_sizes: Vec<usize>,
+    /// Number of bootstrap resamples used to compute the intervals.
+    #[arg(long, default_value_t = 10_000)]
+    pub resamples: usize,
+    /// Numbers of workers of the concurrency benchmarks, which only run
+    /// when set (`--concurrency 1,2,4,8`).
+    #[arg(long, value_delimiter = ',')]
+    pub concurrency: Vec<usize>,
+    /// Writes the results in this JSON file.
+    #[arg(long)]
+    pub output: Option<std::path::PathBuf>,
+    /// Flag added by `cargo bench`.
+    #[arg(long, hide = true)]
+    bench: bool,
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("invalid duration: {s}"))
+}
+
+/**
+ * Dataset size, at least one user: the upserts pick existing users among
+ * them.
+ */
+fn parse_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("the dataset size must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/**
+ * Number of samples, at least two: the standard deviation divides by the
+ * number of samples minus one.
+ */
+fn parse_sample_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(size) if size < 2 => Err("the sample size must be at least 2".to_string()),
+        Ok(size) => Ok(size),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/**
+ * Confidence level, strictly between 0 and 1 (`0.95`, not `95`).
+ */
+fn parse_confidence_level(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(level) if level > 0. && level < 1. => Ok(level),
+        Ok(_) => Err("the confidence level must be between 0 and 1".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        clap::Parser::parse_from(["bench"])
+    }
+}
+
+/**
+ * Number of iterations whose allocations are counted, after the samples
+ * collection.
+ */
+const ALLOCATIONS_ITERS: usize = 10;
+
+/**
+ * Maximum of samples × resamples of the bootstrap, concurrency benchmarks
+ * collect a lot more samples than the others.
+ */
+const BOOTSTRAP_BUDGET: usize = 10_000_000;
+
+/**
+ * Operations whose duration grows with the dataset size, the only ones with
+ * a cost model.
+ */
+const MODELED: [&str; 3] = ["fetch_all", "all_relations", "insert_many"];
+
+/**
+ * Minimum number of dataset sizes of a cost model, a line always fits two
+ * points.
+ */
+const MIN_FIT_SIZES: usize = 3;
+
+pub struct Bencher<'a> {
+    config: &'a Config,
+    samples: Vec<f64>,
+    allocations: Option<Allocations>,
+}
+
+impl<'a> Bencher<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            samples: Vec::new(),
+            allocations: None,
+        }
+    }
+
+    /**
+     * Runs `routine` during the warm up time to estimate its duration, then
+     * collects `sample_size` samples of the per-iteration duration.
+     */
+    pub fn iter<O, F>(&mut self, mut routine: F)
+    where
+        F: FnMut() -> O,
+    {
+        let start = Instant::now();
+        let mut iters = 0;
+
+        loop {
+            std::hint::black_box(routine());
+            iters += 1;
+
+            if start.elapsed() >= self.config.warm_up_time {
+                break;
+            }
+        }
+
+        let iters_per_sample = self.iters_per_sample(start.elapsed(), iters);
+
+        self.samples = (0..self.config.sample_size)
+            .map(|_| {
+                let start = Instant::now();
+
+                for _ in 0..iters_per_sample {
+                    std::hint::black_box(routine());
+                }
+
+                start.elapsed().as_nanos() as f64 / iters_per_sample as f64
+            })
+            .collect();
+
+        if allocator::enabled() {
+            let allocations = (0..ALLOCATIONS_ITERS)
+                .map(|_| {
+                    let counter = Counter::start();
+                    std::hint::black_box(routine());
+                    counter.stop()
+                })
+                .collect::<Vec<_>>();
+
+            self.allocations = Some(median_allocations(&allocations));
+        }
+    }
+
+    /**
+     * Same as [`Bencher::iter`], but `setup` prepares the input of each
+     * iteration outside of the measurement (the rows deleted by the routine
+     * for example). Both closures get `state`, usually the client.
+     */
+    pub fn iter_with_setup<S, I, O, P, R>(&mut self, state: &mut S, mut setup: P, mut routine: R)
+    where
+        P: FnMut(&mut S) -> I,
+        R: FnMut(&mut S, I) -> O,
+    {
+        let start = Instant::now();
+        let mut elapsed = Duration::ZERO;
+        let mut iters = 0;
+
+        loop {
+            let input = setup(state);
+            let call = Instant::now();
+            std::hint::black_box(routine(state, input));
+            elapsed += call.elapsed();
+            iters += 1;
+
+            if start.elapsed() >= self.config.warm_up_time {
+                break;
+            }
+        }
+
+        let iters_per_sample = self.iters_per_sample(elapsed, iters);
+
+        self.samples = (0..self.config.sample_size)
+            .map(|_| {
+                let inputs = (0..iters_per_sample)
+                    .map(|_| setup(state))
+                    .collect::<Vec<_>>();
+                let start = Instant::now();
+
+                for input in inputs {
+                    std::hint::black_box(routine(state, input));
+                }
+
+                start.elapsed().as_nanos() as f64 / iters_per_sample as f64
+            })
+            .collect();
+
+        if allocator::enabled() {
+            let allocations = (0..ALLOCATIONS_ITERS)
+                .map(|_| {
+                    let input = setup(state);
+                    let counter = Counter::start();
+                    std::hint::black_box(routine(state, input));
+                    counter.stop()
+                })
+                .collect::<Vec<_>>();
+
+            self.allocations = Some(median_allocations(&allocations));
+        }
+    }
+
+    /**
+     * Same as [`Bencher::iter`] for an async routine, awaited on the current
+     * runtime.
+     */
+    pub async fn iter_async<O, F>(&mut self, mut routine: F)
+    where
+        F: AsyncFnMut() -> O,
+    {
+        let start = Instant::now();
+        let mut iters = 0;
+
+        loop {
+            std::hint::black_box(routine().await);
+            iters += 1;
+
+            if start.elapsed() >= self.config.warm_up_time {
+                break;
+            }
+        }
+
+        let iters_per_sample = self.iters_per_sample(start.elapsed(), iters);
+        let mut samples = Vec::with_capacity(self.config.sample_size);
+
+        for _ in 0..self.config.sample_size {
+            let start = Instant::now();
+
+            for _ in 0..iters_per_sample {
+                std::hint::black_box(routine().await);
+            }
+
+            samples.push(start.elapsed().as_nanos() as f64 / iters_per_sample as f64);
+        }
+
+        self.samples = samples;
+
+        if allocator::enabled() {
+            let mut allocations = Vec::with_capacity(ALLOCATIONS_ITERS);
+
+            for _ in 0..ALLOCATIONS_ITERS {
+                let counter = Counter::start();
+                std::hint::black_box(routine().await);
+                allocations.push(counter.stop());
+            }
+
+            self.allocations = Some(median_allocations(&allocations));
+        }
+    }
+
+    /**
+     * Same as [`Bencher::iter_with_setup`] for async closures.
+     */
+    pub async fn iter_with_setup_async<S, I, O, P, R>(
+        &mut self,
+        state: &mut S,
+        mut setup: P,
+        mut routine: R,
+    ) where
+        P: AsyncFnMut(&mut S) -> I,
+        R: AsyncFnMut(&mut S, I) -> O,
+    {
+        let start = Instant::now();
+        let mut elapsed = Duration::ZERO;
+        let mut iters = 0;
+
+        loop {
+            let input = setup(state).await;
+            let call = Instant::now();
+            std::hint::black_box(routine(state, input).await);
+            elapsed += call.elapsed();
+            iters += 1;
+
+            if start.elapsed() >= self.config.warm_up_time {
+                break;
+            }
+        }
+
+        let iters_per_sample = self.iters_per_sample(elapsed, iters);
+        let mut samples = Vec::with_capacity(self.config.sample_size);
+
+        for _ in 0..self.config.sample_size {
+            let mut inputs = Vec::with_capacity(iters_per_sample as usize);
+
+            for _ in 0..iters_per_sample {
+                inputs.push(setup(state).await);
+            }
+
+            let start = Instant::now();
+
+            for input in inputs {
+                std::hint::black_box(routine(state, input).await);
+            }
+
+            samples.push(start.elapsed().as_nanos() as f64 / iters_per_sample as f64);
+        }
+
+        self.samples = samples;
+
+        if allocator::enabled() {
+            let mut allocations = Vec::with_capacity(ALLOCATIONS_ITERS);
+
+            for _ in 0..ALLOCATIONS_ITERS {
+                let input = setup(state).await;
+                let counter = Counter::start();
+                std::hint::black_box(routine(state, input).await);
+                allocations.push(counter.stop());
+            }
+
+            self.allocations = Some(median_allocations(&allocations));
+        }
+    }
+
+    /**
+     * Number of iterations per sample to spread the samples over the
+     * measurement time, from the warm up estimation.
+     */
+    fn iters_per_sample(&self, elapsed: Duration, iters: u64) -> u64 {
+        let estimate = elapsed.as_nanos() as f64 / iters as f64;
+        let target =
+            self.config.measurement_time.as_nanos() as f64 / self.config.sample_size as f64;
+
+        (target / estimate).ceil().max(1.) as u64
+    }
+}
+
+/**
+ * Panic of a benchmark, caught to report it as a failure and run the next
+ * benchmarks.
+ */
+struct Panic(String);
+
+impl Panic {
+    fn new(payload: Box<dyn std::any::Any + Send>) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|x| x.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+
+        Self(message)
+    }
+}
+
+impl std::fmt::Debug for Panic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "panicked: {}", self.0)
+    }
+}
+
+fn catch_unwind<T>(f: impl FnOnce() -> T) -> Result<T, Panic> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(Panic::new)
+}
+
+/**
+ * Same as [`catch_unwind`] for a future, each poll is guarded.
+ */
+async fn catch_unwind_async<F: Future>(future: F) -> Result<F::Output, Panic> {
+    let mut future = std::pin::pin!(future);
+
+    std::future::poll_fn(|cx| match catch_unwind(|| future.as_mut().poll(cx)) {
+        Ok(poll) => poll.map(Ok),
+        Err(panic) => Poll::Ready(Err(panic)),
+    })
+    .await
+}
+
+/**
+ * Median of each counter, the first iterations may fill caches (statements,
+ * buffers).
+ */
+fn median_allocations(allocations: &[Allocations]) -> Allocations {
+    let median = |f: fn(&Allocations) -> u64| {
+        let mut values = allocations.iter().map(f).collect::<Vec<_>>();
+        values.sort_unstable();
+
+        values[values.len() / 2]
+    };
+
+    Allocations {
+        count: median(|x| x.count),
+        bytes: median(|x| x.bytes),
+        peak: median(|x| x.peak),
+    }
+}
+
+pub struct Harness {
+    client: String,
+    config: Config,
+    records: Vec<Record>,
+    /**
+     * Operations run on the payload sizes, summarized by their throughput.
+     */
+    payloads: Vec<String>,
+    failed: usize,
+    /**
+     * Cluster started when `DATABASE_URL` isn't set.
+     */
+    cluster: Option<Cluster>,
+}
+
+impl Harness {
+    /**
+     * Creates the harness of a client from the bench binary arguments, the
+     * client is named after its crate (`diesel-bench` is `diesel`).
+     */
+    pub fn from_args(krate: &str) -> Self {
+        Self::new(krate, clap::Parser::parse())
+    }
+
+    pub fn new(krate: &str, config: Config) -> Self {
+        let cluster = match Cluster::start_unless_configured() {
+            Ok(cluster) => cluster,
+            Err(err) => {
+                eprintln!("Unable to start a PostgreSQL cluster: {err}");
+                std::process::exit(1);
+            }
+        };
+
+        Self {
+            client: krate.trim_end_matches("-bench").to_string(),
+            config,
+            records: Vec::new(),
+            payloads: Vec::new(),
+            failed: 0,
+            cluster,
+        }
+    }
+
+    /**
+     * Runs the `operation` benchmark once per configured dataset size. An
+     * error or a panic of `f` fails the benchmark of this size, the next
+     * ones still run.
+     */
+    pub fn bench<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: FnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes(operation, None, self.config.sizes.clone(), f);
+    }
+
+    /**
+     * Same as [`Harness::bench`] for one of the implementations of the
+     * operation by the client.
+     */
+    pub fn bench_implementation<E, F>(
+        &mut self,
+        operation: &str,
+        implementation: &Implementation,
+        f: F,
+    ) where
+        E: std::fmt::Debug,
+        F: FnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes(
+            operation,
+            Some(implementation),
+            self.config.sizes.clone(),
+            f,
+        );
+    }
+
+    /**
+     * Same as [`Harness::bench`] on the large dataset sizes.
+     */
+    pub fn bench_large<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: FnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes(operation, None, self.config.large_sizes.clone(), f);
+    }
+
+    /**
+     * Same as [`Harness::bench`] on the payload sizes, `f` gets the size of
+     * the value in bytes.
+     */
+    pub fn bench_payload<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: FnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.payloads.push(operation.to_string());
+        self.bench_sizes(operation, None, self.config.payload_sizes.clone(), f);
+    }
+
+    fn bench_sizes<E, F>(
+        &mut self,
+        operation: &str,
+        implementation: Option<&Implementation>,
+        sizes: Vec<usize>,
+        mut f: F,
+    ) where
+        E: std::fmt::Debug,
+        F: FnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        let benchmark = benchmark(operation, implementation);
+
+        for size in sizes {
+            let name = format!("{benchmark}/{size}");
+
+            if self.skip(operation, &name) {
+                continue;
+            }
+
+            let mut bencher = Bencher::new(&self.config);
+            let result = match catch_unwind(|| f(&mut bencher, size)) {
+                Ok(result) => result,
+                Err(panic) => {
+                    self.fail(&name, panic);
+                    continue;
+                }
+            };
+
+            self.collect(
+                operation,
+                implementation,
+                size,
+                bencher.samples,
+                bencher.allocations,
+                result,
+            );
+        }
+    }
+
+    pub async fn bench_async<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: AsyncFnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes_async(operation, None, self.config.sizes.clone(), f)
+            .await;
+    }
+
+    pub async fn bench_implementation_async<E, F>(
+        &mut self,
+        operation: &str,
+        implementation: &Implementation,
+        f: F,
+    ) where
+        E: std::fmt::Debug,
+        F: AsyncFnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes_async(
+            operation,
+            Some(implementation),
+            self.config.sizes.clone(),
+            f,
+        )
+        .await;
+    }
+
+    pub async fn bench_large_async<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: AsyncFnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.bench_sizes_async(operation, None, self.config.large_sizes.clone(), f)
+            .await;
+    }
+
+    pub async fn bench_payload_async<E, F>(&mut self, operation: &str, f: F)
+    where
+        E: std::fmt::Debug,
+        F: AsyncFnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        self.payloads.push(operation.to_string());
+        self.bench_sizes_async(operation, None, self.config.payload_sizes.clone(), f)
+            .await;
+    }
+
+    async fn bench_sizes_async<E, F>(
+        &mut self,
+        operation: &str,
+        implementation: Option<&Implementation>,
+        sizes: Vec<usize>,
+        mut f: F,
+    ) where
+        E: std::fmt::Debug,
+        F: AsyncFnMut(&mut Bencher, usize) -> Result<(), E>,
+    {
+        let benchmark = benchmark(operation, implementation);
+
+        for size in sizes {
+            let name = format!("{benchmark}/{size}");
+
+            if self.skip(operation, &name) {
+                continue;
+            }
+
+            let mut bencher = Bencher::new(&self.config);
+            let result = match catch_unwind_async(f(&mut bencher, size)).await {
+                Ok(result) => result,
+                Err(panic) => {
+                    self.fail(&name, panic);
+                    continue;
+                }
+            };
+
+            self.collect(
+                operation,
+                implementation,
+                size,
+                bencher.samples,
+                bencher.allocations,
+                result,
+            );
+        }
+    }
+
+    /**
+     * Runs the concurrency benchmarks, once per dataset size and number of
+     * workers. Each worker is a thread calling the operation in a loop.
+     */
+    pub fn bench_concurrency<C: Client + Send>(&mut self)
+    where
+        C::Error: Send,
+    {
+        for operation in Operation::ALL {
+            for size in self.config.sizes.clone() {
+                for threads in self.config.concurrency.clone() {
+                    let name = format!("{}/{size}/{threads}", operation.name());
+
+                    if self.skip(operation.name(), &name) {
+                        continue;
+                    }
+
+                    let result = catch_unwind(|| {
+                        let client = C::setup(size)?;
+                        let workers = (0..threads)
+                            .map(|_| client.worker())
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let measure = concurrency::run(
+                            workers,
+                            operation,
+                            self.config.warm_up_time,
+                            self.config.measurement_time,
+                        );
+
+                        drop(client);
+
+                        measure
+                    });
+
+                    match result {
+                        Ok(result) => self.collect_concurrency(operation, size, threads, result),
+                        Err(panic) => self.fail(&name, panic),
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Same as [`Harness::bench_concurrency`] for an async client, each
+     * worker is a task of the current runtime.
+     */
+    pub async fn bench_concurrency_async<C: AsyncClient + 'static>(&mut self)
+    where
+        C::Error: 'static,
+    {
+        for operation in Operation::ALL {
+            for size in self.config.sizes.clone() {
+                for threads in self.config.concurrency.clone() {
+                    let name = format!("{}/{size}/{threads}", operation.name());
+
+                    if self.skip(operation.name(), &name) {
+                        continue;
+                    }
+
+                    let result = catch_unwind_async(async {
+                        let client = C::setup(size).await?;
+                        let mut workers = Vec::with_capacity(threads);
+
+                        for _ in 0..threads {
+                            workers.push(client.worker().await?);
+                        }
+
+                        let measure = concurrency::run_async(
+                            workers,
+                            operation,
+                            self.config.warm_up_time,
+                            self.config.measurement_time,
+                        )
+                        .await;
+
+                        drop(client);
+
+                        measure
+                    })
+                    .await;
+
+                    match result {
+                        Ok(result) => self.collect_concurrency(operation, size, threads, result),
+                        Err(panic) => self.fail(&name, panic),
+                    }
+                }
+            }
+        }
+    }
+
+    fn skip(&self, operation: &str, name: &str) -> bool {
+        if !self.config.operations.is_empty()
+            && !self.config.operations.iter().any(|x| x == operation)
+        {
+            return true;
+        }
+
+        self.config
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn collect<E: std::fmt::Debug>(
+        &mut self,
+        operation: &str,
+        implementation: Option<&Implementation>,
+        size: usize,
+        samples: Vec<f64>,
+        allocations: Option<Allocations>,
+        result: Result<(), E>,
+    ) {
+        let name = format!("{}/{size}", benchmark(operation, implementation));
+
+        if let Err(err) = result {
+            self.fail(&name, err);
+            return;
+        }
+
+        if samples.is_empty() {
+            self.fail(&name, "no sample collected");
+            return;
+        }
+
+        let stats = Stats::new(
+            &samples,
+            self.config.confidence_level,
+            self.config.resamples,
+        );
+
+        self.report(&name, samples.len(), &stats, allocations.as_ref());
+
+        self.records.push(Record {
+            client: self.client.clone(),
+            operation: operation.to_string(),
+            size,
+            samples,
+            stats,
+            allocations,
+            concurrency: None,
+            variant: implementation.map(|x| Variant {
+                name: x.name.to_string(),
+                style: x.style,
+            }),
+        });
+    }
+
+    fn collect_concurrency<E: std::fmt::Debug>(
+        &mut self,
+        operation: Operation,
+        size: usize,
+        threads: usize,
+        result: Result<Measure, E>,
+    ) {
+        let name = format!("{}/{size}/{threads}", operation.name());
+
+        let measure = match result {
+            Ok(measure) => measure,
+            Err(err) => {
+                self.fail(&name, err);
+                return;
+            }
+        };
+
+        if measure.samples.is_empty() {
+            self.fail(&name, "no call completed");
+            return;
+        }
+
+        let stats = Stats::new(
+            &measure.samples,
+            self.config.confidence_level,
+            self.config
+                .resamples
+                .min(BOOTSTRAP_BUDGET / measure.samples.len()),
+        );
+
+        self.report(&name, measure.samples.len(), &stats, None);
+        println!("    {threads} workers · {:.0} calls/s", measure.throughput);
+
+        self.records.push(Record {
+            client: self.client.clone(),
+            operation: operation.name().to_string(),
+            size,
+            samples: measure.samples,
+            stats,
+            allocations: None,
+            concurrency: Some(Concurrency {
+                threads,
+                throughput: measure.throughput,
+            }),
+            variant: None,
+        });
+    }
+
+    fn fail<E: std::fmt::Debug>(&mut self, name: &str, err: E) {
+        eprintln!("test {name} ... FAILED: {err:?}");
+        self.failed += 1;
+    }
+
+    fn report(&self, name: &str, samples: usize, stats: &Stats, allocations: Option<&Allocations>) {
+        let d = stats::format_duration;
+
+        println!(
+            "test {name} ... bench: {:>14} ns/iter (+/- {})",
+            thousands(stats.median),
+            thousands(stats.std_dev)
+        );
+        println!(
+            "    median {} [{} {}]",
+            d(stats.median),
+            d(stats.median_ci.lower),
+            d(stats.median_ci.upper)
+        );
+        println!(
+            "    mean   {} [{} {}] ± {}",
+            d(stats.mean),
+            d(stats.mean_ci.lower),
+            d(stats.mean_ci.upper),
+            d(stats.std_dev)
+        );
+        println!(
+            "    p5 {} · p25 {} · p75 {} · p95 {} · p99 {} · min {} · max {}",
+            d(stats.p5),
+            d(stats.p25),
+            d(stats.p75),
+            d(stats.p95),
+            d(stats.p99),
+            d(stats.min),
+            d(stats.max)
+        );
+
+        if let Some(allocations) = allocations {
+            println!(
+                "    {} allocations · {} allocated · {} peak",
+                allocations.count,
+                allocator::format_bytes(allocations.bytes),
+                allocator::format_bytes(allocations.peak)
+            );
+        }
+
+        let outliers = &stats.outliers;
+
+        if outliers.total() > 0 {
+            println!(
+                "    {} outliers among {} samples ({} low severe, {} low mild, {} high mild, {} high severe)",
+                outliers.total(),
+                samples,
+                outliers.low_severe,
+                outliers.low_mild,
+                outliers.high_mild,
+                outliers.high_severe
+            );
+        }
+    }
+
+    /**
+     * Median duration of each operation by dataset size, with the growth
+     * factor between the smallest and the largest size.
+     */
+    fn scaling(&self) {
+        let sizes = &self.config.sizes;
+
+        if sizes.len() < 2 || self.records.is_empty() {
+            return;
+        }
+
+        print!("\n{:<24}", "scaling");
+        for size in sizes {
+            print!("{size:>14}");
+        }
+        println!("{:>10}", "growth");
+
+        for operation in self.operations() {
+            let medians = sizes
+                .iter()
+                .map(|size| {
+                    self.records
+                        .iter()
+                        .find(|x| {
+                            x.benchmark() == operation && x.size == *size && x.concurrency.is_none()
+                        })
+                        .map(|x| x.stats.median)
+                })
+                .collect::<Vec<_>>();
+
+            // Operations run on other sizes, like the large ones.
+            if medians.iter().all(Option::is_none) {
+                continue;
+            }
+
+            print!("{operation:<24}");
+            for median in &medians {
+                let median = median.map(stats::format_duration).unwrap_or_default();
+                print!("{median:>14}");
+            }
+
+            let measured = medians.iter().flatten().collect::<Vec<_>>();
+            if let (Some(first), Some(last)) = (measured.first(), measured.last()) {
+                print!("{:>10}", format!("×{:.1}", *last / *first));
+            }
+            println!();
+        }
+    }
+
+    /**
+     * Linear model of each operation duration by dataset size, to separate
+     * the fixed per-call overhead (round-trip, statement preparation) from
+     * the marginal cost of each row (decoding, allocation). `None` with less
+     * than [`MIN_FIT_SIZES`] dataset sizes.
+     */
+    pub fn fit(&self, operation: &str) -> Option<stats::Fit> {
+        let mut points = self
+            .records
+            .iter()
+            .filter(|x| x.benchmark() == operation && x.concurrency.is_none())
+            .map(|x| (x.size as f64, x.stats.median))
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|a, b| a.0 == b.0);
+
+        if points.len() < MIN_FIT_SIZES {
+            return None;
+        }
+
+        stats::fit(&points)
+    }
+
+    fn cost_model(&self) {
+        let fits = self
+            .operations()
+            .into_iter()
+            .filter(|operation| {
+                let name = operation.split('/').next().unwrap_or_default();
+
+                MODELED.contains(&name)
+            })
+            .filter_map(|operation| self.fit(&operation).map(|fit| (operation, fit)))
+            .collect::<Vec<_>>();
+
+        if fits.is_empty() {
+            return;
+        }
+
+        println!(
+            "\n{:<24}{:>14}{:>14}{:>10}",
+            "cost model", "overhead", "per row", "r²"
+        );
+
+        for (operation, fit) in fits {
+            println!(
+                "{operation:<24}{:>14}{:>14}{:>10.3}",
+                stats::format_duration(fit.overhead),
+                stats::format_duration(fit.per_row),
+                fit.r_squared
+            );
+        }
+    }
+
+    /**
+     * Throughput and tail latency of each concurrency benchmark, by number of
+     * workers.
+     */
+    fn concurrency(&self) {
+        let records = self
+            .records
+            .iter()
+            .filter_map(|x| x.concurrency.map(|concurrency| (x, concurrency)))
+            .collect::<Vec<_>>();
+
+        if records.is_empty() {
+            return;
+        }
+
+        println!(
+            "\n{:<32}{:>8}{:>14}{:>14}{:>14}{:>14}",
+            "concurrency", "workers", "calls/s", "p50", "p99", "max"
+        );
+
+        for (record, concurrency) in records {
+            println!(
+                "{:<32}{:>8}{:>14.0}{:>14}{:>14}{:>14}",
+                format!("{}/{}", record.operation, record.size),
+                concurrency.threads,
+                concurrency.throughput,
+                stats::format_duration(record.stats.median),
+                stats::format_duration(record.stats.p99),
+                stats::format_duration(record.stats.max)
+            );
+        }
+    }
+
+    /**
+     * Bytes per second of each payload benchmark, with the peak of live
+     * bytes of one iteration when the allocations are counted.
+     */
+    fn throughput(&self) {
+        let records = self
+            .records
+            .iter()
+            .filter(|x| self.payloads.contains(&x.operation))
+            .collect::<Vec<_>>();
+
+        if records.is_empty() {
+            return;
+        }
+
+        println!(
+            "\n{:<32}{:>14}{:>14}{:>14}",
+            "payload", "median", "throughput", "peak"
+        );
+
+        for record in records {
+            let throughput = record.size as f64 / (record.stats.median / 1_000_000_000.);
+            let peak = record
+                .allocations
+                .map(|x| allocator::format_bytes(x.peak))
+                .unwrap_or_default();
+
+            println!(
+                "{:<32}{:>14}{:>14}{:>14}",
+                record.name(),
+                stats::format_duration(record.stats.median),
+                format!("{}/s", allocator::format_bytes(throughput as u64)),
+                peak
+            );
+        }
+    }
+
+    /**
+     * Benchmarks run on the dataset sizes, each implementation of an
+     * operation apart.
+     */
+    fn operations(&self) -> Vec<String> {
+        let mut operations = self
+            .records
+            .iter()
+            .filter(|x| x.concurrency.is_none())
+            .map(Record::benchmark)
+            .collect::<Vec<_>>();
+        operations.dedup();
+
+        operations
+    }
+
+    /**
+     * Prints the scaling summary, the cost models, the payloads throughput
+     * and the concurrency summary, writes the results file, then exits with
+     * a failure code if one of the benchmarks failed.
+     */
+    pub fn finish(mut self) {
+        // Stopped here, `process::exit` doesn't run the destructors.
+        self.cluster.take();
+
+        self.scaling();
+        self.cost_model();
+        self.throughput();
+        self.concurrency();
+
+        if let Some(output) = &self.config.output {
+            let results = Results::new(self.records.clone());
+
+            if let Err(err) = results.save(output) {
+                eprintln!("Unable to write {}: {err}", output.display());
+                std::process::exit(1);
+            }
+        }
+
+        if self.failed > 0 {
+            eprintln!("{} benchmark(s) failed", self.failed);
+            std::process::exit(1);
+        }
+    }
+}
+
+/**
+ * Name of the benchmark of `operation`, followed by the name of the
+ * implementation if any.
+ */
+fn benchmark(operation: &str, implementation: Option<&Implementation>) -> String {
+    match implementation {
+        Some(implementation) => format!("{operation}/{}", implementation.name),
+        None => operation.to_string(),
+    }
+}
+
+/**
+ * Formats a number like libtest does (`12,345`), so the `graph` script can
+ * parse the summary line.
+ */
+fn thousands(n: f64) -> String {
+    let digits = (n.round() as u64).to_string();
+    let mut s = String::new();
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(',');
+        }
+        s.push(c);
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use clap::Parser;
+
+    #[test]
+    fn sizes() {
+        let config = Config::try_parse_from(["bench", "--sizes", "1,100"]).unwrap();
+
+        assert_eq!(config.sizes, [1, 100]);
+        assert!(Config::try_parse_from(["bench", "--sizes", "0"]).is_err());
+        assert!(Config::try_parse_from(["bench", "--sizes", "10,0"]).is_err());
+        assert!(Config::try_parse_from(["bench", "--large-sizes", "0"]).is_err());
+    }
+
+    #[test]
+    fn sample_size() {
+        let config = Config::try_parse_from(["bench", "--sample-size", "2"]).unwrap();
+
+        assert_eq!(config.sample_size, 2);
+        assert!(Config::try_parse_from(["bench", "--sample-size", "0"]).is_err());
+        assert!(Config::try_parse_from(["bench", "--sample-size", "1"]).is_err());
+    }
+
+    #[test]
+    fn confidence_level() {
+        let config = Config::try_parse_from(["bench", "--confidence-level", "0.99"]).unwrap();
+
+        assert_eq!(config.confidence_level, 0.99);
+
+        for level in ["0", "1", "95", "-0.5", "NaN"] {
+            assert!(
+                Config::try_parse_from(["bench", "--confidence-level", level]).is_err(),
+                "{level}"
+            );
+        }
+    }
+}
@@ -1,16 +1,333 @@
-#![feature(test)]
-#![allow(soft_unstable)]
 #![allow(dead_code)]
 
-extern crate test;
+pub mod allocator;
+pub mod cluster;
+pub mod compare;
+pub mod concurrency;
+pub mod dataset;
+pub mod fixture;
+pub mod harness;
+pub mod results;
+pub mod stats;
+pub mod types;
+
+pub use tokio;
 
 // "85e11126-a41d-4dce-98f8-731a87685d2c"
 pub const UUID: uuid::Uuid = uuid::Uuid::from_u128(177955938094988552825808298658849381676);
 
+/**
+ * Number of users created by `setup(n)`: the `n` generated ones plus the
+ * one identified by [`UUID`].
+ */
+pub fn rows(n: usize) -> usize {
+    n + 1
+}
+
+/**
+ * Number of posts of each user created by `setup(n)`.
+ */
+pub const POSTS: usize = 30;
+
+/**
+ * Value of `size` bytes stored by the payloads benchmarks, the same as the
+ * one created by `setup_payloads(size)`. Printable, to be both a text and a
+ * bytea.
+ */
+pub fn payload(size: usize) -> String {
+    (0..size)
+        .map(|x| b"0123456789abcdef"[x % 16] as char)
+        .collect()
+}
+
+/**
+ * Key of the `i`th upsert on a dataset of `n` users created by
+ * `setup_keyed(n)`: one of these users for even `i`, a new one otherwise,
+ * so half the keys already exist.
+ */
+pub fn upsert_key(n: usize, i: usize) -> uuid::Uuid {
+    if i.is_multiple_of(2) {
+        uuid::Uuid::from_u128((i / 2 % n) as u128 + 1)
+    } else {
+        uuid::Uuid::from_u128(fastrand::u128(..))
+    }
+}
+
+/**
+ * Keys of a batch of `n` upserts, see [`upsert_key`].
+ */
+pub fn upsert_keys(n: usize) -> Vec<uuid::Uuid> {
+    (0..n).map(|i| upsert_key(n, i)).collect()
+}
+
+/**
+ * Number of inserts, and of lookups, of the pipeline benchmark.
+ */
+pub const PIPELINE_SIZE: usize = 100;
+
+/**
+ * Number of users of a page of the pagination benchmarks.
+ */
+pub const PAGE_SIZE: usize = 50;
+
+/**
+ * Page fetched by the pagination benchmarks on a dataset of `n` users, the
+ * last full one (or the first one), numbered from 1.
+ */
+pub fn page(n: usize) -> usize {
+    (rows(n) / PAGE_SIZE).max(1)
+}
+
+/**
+ * Deletes the users added by the insert benchmarks, named `User` unlike the
+ * generated ones, to give back the table of `setup(n)`.
+ */
+pub const DELETE_INSERTED: &str = "delete from users where name = 'User'";
+
+/**
+ * Id of the last user of the page before `page` on a dataset created by
+ * `setup_keyed`, where a keyset pagination starts.
+ */
+pub fn page_key(page: usize) -> uuid::Uuid {
+    uuid::Uuid::from_u128(((page - 1) * PAGE_SIZE) as u128)
+}
+
+/**
+ * Connection string of the benchmark database: `DATABASE_URL`, else the
+ * cluster started by the harness.
+ */
+pub fn dsn() -> String {
+    std::env::var("DATABASE_URL")
+        .ok()
+        .or_else(cluster::dsn)
+        .expect("DATABASE_URL isn't set")
+}
+
+/**
+ * How the statement of a query is sent to the server.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /**
+     * Simple query protocol, the parameters are inlined in the query text.
+     */
+    Simple,
+    /**
+     * Extended query protocol with a new unnamed statement for each query.
+     */
+    Unprepared,
+    /**
+     * Statement prepared once and reused.
+     */
+    Prepared,
+}
+
+impl Mode {
+    pub const ALL: [Self; 3] = [Self::Simple, Self::Unprepared, Self::Prepared];
+
+    /**
+     * Name of the benchmark running the lookup in this mode.
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Simple => "simple_query",
+            Self::Unprepared => "unprepared_query",
+            Self::Prepared => "prepared_query",
+        }
+    }
+}
+
+/**
+ * How a page of users, ordered by id, is fetched.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Pagination {
+    /**
+     * `OFFSET` and `LIMIT`, the server reads and discards the previous
+     * pages.
+     */
+    Offset,
+    /**
+     * `WHERE id > $1` with the id of the last user of the previous page.
+     */
+    Keyset,
+    /**
+     * Server-side cursor moved to the page in a transaction.
+     */
+    Cursor,
+}
+
+impl Pagination {
+    pub const ALL: [Self; 3] = [Self::Offset, Self::Keyset, Self::Cursor];
+
+    /**
+     * Name of the benchmark fetching a page with this strategy.
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Offset => "paginate_offset",
+            Self::Keyset => "paginate_keyset",
+            Self::Cursor => "paginate_cursor",
+        }
+    }
+}
+
+/**
+ * One of the implementations of an operation by a client, when the client
+ * API offers several ways to do it.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Implementation {
+    pub name: &'static str,
+    pub style: results::Style,
+}
+
+impl Implementation {
+    /**
+     * The operation repeated once per row.
+     */
+    pub const LOOP: Self = Self::idiomatic("loop");
+
+    /**
+     * Queries sent one after the other, waiting for each reply.
+     */
+    pub const SERIAL: Self = Self::idiomatic("serial");
+
+    /**
+     * The straightforward use of the client API.
+     */
+    pub const fn idiomatic(name: &'static str) -> Self {
+        Self {
+            name,
+            style: results::Style::Idiomatic,
+        }
+    }
+
+    /**
+     * The fastest way the client API allows.
+     */
+    pub const fn optimized(name: &'static str) -> Self {
+        Self {
+            name,
+            style: results::Style::Optimized,
+        }
+    }
+}
+
+/**
+ * Data format of a `COPY`.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CopyFormat {
+    Text,
+    Csv,
+    Binary,
+}
+
+impl CopyFormat {
+    pub const ALL: [Self; 3] = [Self::Text, Self::Csv, Self::Binary];
+
+    /**
+     * Value of the `format` option of the `COPY` statement.
+     */
+    pub fn option(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Csv => "csv",
+            Self::Binary => "binary",
+        }
+    }
+
+    /**
+     * Name of the benchmark copying users in the table in this format.
+     */
+    pub fn copy_in(&self) -> &'static str {
+        match self {
+            Self::Text => "copy_in_text",
+            Self::Csv => "copy_in_csv",
+            Self::Binary => "copy_in_binary",
+        }
+    }
+
+    /**
+     * Name of the benchmark copying the users table out in this format.
+     */
+    pub fn copy_out(&self) -> &'static str {
+        match self {
+            Self::Text => "copy_out_text",
+            Self::Csv => "copy_out_csv",
+            Self::Binary => "copy_out_binary",
+        }
+    }
+
+    /**
+     * Statement of the copy in benchmark.
+     */
+    pub fn copy_in_statement(&self) -> String {
+        format!(
+            "COPY users (name, hair_color) FROM STDIN (FORMAT {})",
+            self.option()
+        )
+    }
+
+    /**
+     * Statement of the copy out benchmark.
+     */
+    pub fn copy_out_statement(&self) -> String {
+        format!("COPY users TO STDOUT (FORMAT {})", self.option())
+    }
+
+    /**
+     * `n` users, the `name` and `hair_color` columns of the copy in
+     * statement, in this format. For the clients without an API encoding the
+     * rows.
+     */
+    pub fn users(&self, n: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        match self {
+            Self::Text => {
+                for _ in 0..n {
+                    data.extend_from_slice(b"User\thair color\n");
+                }
+            }
+            Self::Csv => {
+                for _ in 0..n {
+                    data.extend_from_slice(b"User,hair color\n");
+                }
+            }
+            Self::Binary => {
+                // Signature, flags and header extension length.
+                data.extend_from_slice(b"PGCOPY\n\xff\r\n\0");
+                data.extend_from_slice(&0_i32.to_be_bytes());
+                data.extend_from_slice(&0_i32.to_be_bytes());
+
+                for _ in 0..n {
+                    data.extend_from_slice(&2_i16.to_be_bytes());
+
+                    for value in [&b"User"[..], b"hair color"] {
+                        data.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                        data.extend_from_slice(value);
+                    }
+                }
+
+                data.extend_from_slice(&(-1_i16).to_be_bytes());
+            }
+        }
+
+        data
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub trait Client: Sized {
-    type Error: Sized;
+    type Error: std::fmt::Debug;
     type User: Sized;
     type Post: Sized;
+    /**
+     * Row of the types table.
+     */
+    type Types: Sized;
 
     /**
      * Creates a new database connection.
@@ -38,117 +355,1644 @@ pub trait Client: Sized {
     fn fetch_first(&mut self) -> Result<Self::User, Self::Error>;
 
     /**
-     * Fetch only the last result of a rows set.
+     * Fetch only the last result of a set of `rows` rows.
      */
-    fn fetch_last(&mut self) -> Result<Self::User, Self::Error>;
+    fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error>;
 
     fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error>;
 
     fn all_relations(&mut self) -> Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error>;
 
-    fn setup(n: usize) -> Result<Self, Self::Error> {
-        env_logger::try_init().ok();
+    /**
+     * Update the name and hair color of the user identified by [`UUID`].
+     */
+    fn update_user(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Update the hair color of all users.
+     */
+    fn update_users(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Delete the user `id` by its primary key, its posts are deleted by the
+     * cascade of `posts.author`.
+     */
+    fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error>;
+
+    /**
+     * Insert the user `id`, or update its name and hair color when it
+     * already exists.
+     */
+    fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error>;
+
+    /**
+     * Upsert the users `ids` in one statement, see [`Client::upsert_user`].
+     */
+    fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error>;
+
+    /**
+     * Insert a user and update the hair color of the user identified by
+     * [`UUID`] in a committed transaction.
+     */
+    fn transaction(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Insert a user in a rolled back transaction.
+     */
+    fn rollback(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Insert a user in a transaction, another one in a savepoint and a last
+     * one in a nested savepoint rolled back, then commit.
+     */
+    fn savepoints(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Modes of [`Client::lookup`] the client API can express.
+     */
+    fn modes() -> &'static [Mode];
+
+    /**
+     * Fetch the name of the user identified by [`UUID`], the statement is
+     * sent to the server as `mode`.
+     */
+    fn lookup(&mut self, mode: Mode) -> Result<String, Self::Error>;
+
+    /**
+     * Reads the users one row at a time, without loading the whole result
+     * set, and returns the total length of their names.
+     */
+    fn stream(&mut self) -> Result<usize, Self::Error>;
+
+    /**
+     * Fetch all rows of the types table.
+     */
+    fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error>;
+
+    /**
+     * Insert one row in the types table.
+     */
+    fn insert_types(&mut self) -> Result<(), Self::Error>;
+
+    /**
+     * Insert one row in the payloads table with `data` as bytea.
+     */
+    fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+
+    /**
+     * Fetch the bytea payload created by `setup_payloads` and returns its
+     * length.
+     */
+    fn fetch_bytea(&mut self) -> Result<usize, Self::Error>;
+
+    /**
+     * Insert one row in the payloads table with `content` as text.
+     */
+    fn insert_text(&mut self, content: &str) -> Result<(), Self::Error>;
+
+    /**
+     * Fetch the text payload created by `setup_payloads` and returns its
+     * length.
+     */
+    fn fetch_text(&mut self) -> Result<usize, Self::Error>;
+
+    /**
+     * Pagination strategies of [`Client::paginate`] the client API can
+     * express.
+     */
+    fn paginations() -> &'static [Pagination];
+
+    /**
+     * Fetch the [`PAGE_SIZE`] users of `page`, numbered from 1, by id order.
+     * The keyset pagination starts after [`page_key`].
+     */
+    fn paginate(
+        &mut self,
+        pagination: Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error>;
 
-        let dsn = std::env::var("DATABASE_URL").unwrap();
+    /**
+     * Implementations of [`Client::insert_many`], a loop on
+     * [`Client::insert_user`] by default.
+     */
+    fn insert_many_implementations() -> &'static [Implementation] {
+        &[Implementation::LOOP]
+    }
 
-        let mut conn = Self::create(&dsn)?;
+    /**
+     * Insert `n` users with `implementation`, one of
+     * [`Client::insert_many_implementations`].
+     */
+    fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        debug_assert_eq!(implementation, Implementation::LOOP.name);
 
-        conn.exec(&format!(include_str!("sql/structure.sql"), n))?;
+        self.insert_users(n)
+    }
 
-        Ok(conn)
+    /**
+     * Implementations of [`Client::pipeline`], [`Client::serial`] by
+     * default.
+     */
+    fn pipeline_implementations() -> &'static [Implementation] {
+        &[Implementation::SERIAL]
     }
 
-    fn tear_down(&mut self) -> Result<(), Self::Error> {
-        self.exec("DROP TABLE IF EXISTS posts").map(|_| ())?;
-        self.exec("DROP TABLE IF EXISTS users").map(|_| ())?;
+    /**
+     * Insert [`PIPELINE_SIZE`] users, then look up as many times the name of
+     * the user identified by [`UUID`], with `implementation`, one of
+     * [`Client::pipeline_implementations`]. Returns the length of the names.
+     */
+    fn pipeline(&mut self, implementation: &str) -> Result<usize, Self::Error> {
+        debug_assert_eq!(implementation, Implementation::SERIAL.name);
 
-        Ok(())
+        self.serial()
     }
 
-    fn insert_users(&mut self, n: usize) -> Result<(), Self::Error> {
-        for _ in 0..n {
+    /**
+     * The work of [`Client::pipeline`] through the other calls of this
+     * trait, each one waiting for its reply.
+     */
+    fn serial(&mut self) -> Result<usize, Self::Error> {
+        for _ in 0..PIPELINE_SIZE {
             self.insert_user()?;
         }
 
-        Ok(())
+        (0..PIPELINE_SIZE).try_fold(0, |len, _| Ok(len + self.lookup(Mode::Unprepared)?.len()))
     }
-}
 
-#[macro_export]
-macro_rules! bench {
-    ($ty:ty) => {
-        use $crate::Client;
+    /**
+     * Formats of [`Client::copy_in`] the client API can express.
+     */
+    fn copy_in_formats() -> &'static [CopyFormat];
 
-        #[bench]
-        fn query_one(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(1)?;
+    /**
+     * Insert `n` users with a `COPY FROM STDIN` in `format`.
+     */
+    fn copy_in(&mut self, format: CopyFormat, n: usize) -> Result<(), Self::Error>;
 
-            b.iter(|| client.fetch_all().unwrap());
+    /**
+     * Formats of [`Client::copy_out`] the client API can express.
+     */
+    fn copy_out_formats() -> &'static [CopyFormat];
 
-            client.tear_down()
-        }
+    /**
+     * Reads the users table with a `COPY TO STDOUT` in `format` and returns
+     * the number of bytes received.
+     */
+    fn copy_out(&mut self, format: CopyFormat) -> Result<usize, Self::Error>;
 
-        #[bench]
-        fn query_all(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(10_000)?;
+    /**
+     * Loads the generated `users` and their `posts` with `COPY … FROM
+     * STDIN`, to build the template databases (see [`dataset`]).
+     */
+    fn load(&mut self, users: &[dataset::User], posts: &[dataset::Post])
+    -> Result<(), Self::Error>;
 
-            b.iter(|| client.fetch_all().unwrap());
+    /**
+     * Connection of a concurrency benchmark worker, to `dsn`. Each worker
+     * opens its own connection by default, clients built on a shareable pool
+     * give a handle on it instead.
+     */
+    fn worker(&self, dsn: &str) -> Result<Self, Self::Error> {
+        Self::create(dsn)
+    }
 
-            client.tear_down()
-        }
+    /**
+     * Creates the users and posts tables, with `n` users and [`POSTS`] posts
+     * each, in the database of a new fixture.
+     */
+    fn setup(n: usize) -> Result<fixture::Fixture<Self>, Self::Error> {
+        Self::setup_with_posts(n, POSTS)
+    }
+
+    /**
+     * Same as [`Client::setup`] with `posts` posts per user.
+     */
+    fn setup_with_posts(n: usize, posts: usize) -> Result<fixture::Fixture<Self>, Self::Error> {
+        fixture::Fixture::create(
+            include_str!("sql/structure.sql"),
+            Some(dataset::Dataset::new(n, posts)),
+        )
+    }
+
+    /**
+     * Creates the types table, which covers the data types beyond the users
+     * and posts ones, with `n` rows.
+     */
+    fn setup_types(n: usize) -> Result<fixture::Fixture<Self>, Self::Error> {
+        fixture::Fixture::create(&format!(include_str!("sql/types.sql"), n), None)
+    }
 
-        #[bench]
-        fn insert_one(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(0)?;
+    /**
+     * Creates the payloads table, with one row of a `size` bytes bytea and
+     * text.
+     */
+    fn setup_payloads(size: usize) -> Result<fixture::Fixture<Self>, Self::Error> {
+        fixture::Fixture::create(
+            &format!(include_str!("sql/payloads.sql"), size = size),
+            None,
+        )
+    }
 
-            b.iter(|| client.insert_users(1).unwrap());
+    /**
+     * Creates the users table with `n` users, without posts, identified by
+     * `Uuid::from_u128(1..=n)`, the user identified by [`UUID`] sorts after
+     * them.
+     */
+    fn setup_keyed(n: usize) -> Result<fixture::Fixture<Self>, Self::Error> {
+        fixture::Fixture::create(
+            include_str!("sql/structure.sql"),
+            Some(dataset::Dataset::keyed(n)),
+        )
+    }
 
-            client.tear_down()
+    /**
+     * Insert `n` users one at a time.
+     */
+    fn insert_users(&mut self, n: usize) -> Result<(), Self::Error> {
+        for _ in 0..n {
+            self.insert_user()?;
         }
 
-        #[bench]
-        fn insert_many(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(0)?;
+        Ok(())
+    }
 
-            b.iter(|| client.insert_users(25).unwrap());
+    /**
+     * Creates a user with `posts` posts and returns its id, to prepare the
+     * rows deleted by a benchmark.
+     */
+    fn create_user(&mut self, posts: usize) -> Result<uuid::Uuid, Self::Error> {
+        let id = uuid::Uuid::from_u128(fastrand::u128(..));
 
-            client.tear_down()
-        }
+        self.exec(&format!(
+            include_str!("sql/user.sql"),
+            id = id,
+            posts = posts
+        ))?;
 
-        #[bench]
-        fn fetch_first(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(10_000)?;
+        Ok(id)
+    }
+}
 
-            b.iter(|| client.fetch_first().unwrap());
+/**
+ * Async version of [`Client`], for clients only available through an async
+ * API. Futures are `Send` to be usable on a multi-threaded runtime.
+ */
+#[allow(clippy::type_complexity)]
+pub trait AsyncClient: Sized + Send {
+    type Error: std::fmt::Debug + Send;
+    type User: Sized + Send;
+    type Post: Sized + Send;
+    /**
+     * Row of the types table.
+     */
+    type Types: Sized + Send;
 
-            client.tear_down()
-        }
+    /**
+     * Creates a new database connection.
+     */
+    fn create(dsn: &str) -> impl Future<Output = Result<Self, Self::Error>> + Send;
 
-        #[bench]
-        fn fetch_last(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(10_000)?;
+    /**
+     * Execute a simple query (used to create and drop table).
+     */
+    fn exec(&mut self, query: &str) -> impl Future<Output = Result<(), Self::Error>> + Send;
 
-            b.iter(|| client.fetch_last().unwrap());
+    /**
+     * Insert one row in user table.
+     */
+    fn insert_user(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
 
-            client.tear_down()
-        }
+    /**
+     * Fetch all rows of a table.
+     */
+    fn fetch_all(&mut self) -> impl Future<Output = Result<Vec<Self::User>, Self::Error>> + Send;
 
-        #[bench]
-        fn one_relation(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(300)?;
+    /**
+     * Fetch only the first result of a rows set.
+     */
+    fn fetch_first(&mut self) -> impl Future<Output = Result<Self::User, Self::Error>> + Send;
 
-            b.iter(|| client.one_relation().unwrap());
+    /**
+     * Fetch only the last result of a set of `rows` rows.
+     */
+    fn fetch_last(
+        &mut self,
+        rows: usize,
+    ) -> impl Future<Output = Result<Self::User, Self::Error>> + Send;
 
-            client.tear_down()
-        }
+    fn one_relation(
+        &mut self,
+    ) -> impl Future<Output = Result<(Self::User, Vec<Self::Post>), Self::Error>> + Send;
 
-        #[bench]
-        fn all_relations(b: &mut test::Bencher) -> Result<(), <$ty as $crate::Client>::Error> {
-            let mut client: $ty = Client::setup(300)?;
+    fn all_relations(
+        &mut self,
+    ) -> impl Future<Output = Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error>> + Send;
 
-            b.iter(|| client.all_relations().unwrap());
+    /**
+     * Update the name and hair color of the user identified by [`UUID`].
+     */
+    fn update_user(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Update the hair color of all users.
+     */
+    fn update_users(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Delete the user `id` by its primary key, its posts are deleted by the
+     * cascade of `posts.author`.
+     */
+    fn delete_user(
+        &mut self,
+        id: uuid::Uuid,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Insert the user `id`, or update its name and hair color when it
+     * already exists.
+     */
+    fn upsert_user(
+        &mut self,
+        id: uuid::Uuid,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Upsert the users `ids` in one statement, see
+     * [`AsyncClient::upsert_user`].
+     */
+    fn upsert_users(
+        &mut self,
+        ids: &[uuid::Uuid],
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Insert a user and update the hair color of the user identified by
+     * [`UUID`] in a committed transaction.
+     */
+    fn transaction(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Insert a user in a rolled back transaction.
+     */
+    fn rollback(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Insert a user in a transaction, another one in a savepoint and a last
+     * one in a nested savepoint rolled back, then commit.
+     */
+    fn savepoints(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Modes of [`AsyncClient::lookup`] the client API can express.
+     */
+    fn modes() -> &'static [Mode];
+
+    /**
+     * Fetch the name of the user identified by [`UUID`], the statement is
+     * sent to the server as `mode`.
+     */
+    fn lookup(&mut self, mode: Mode) -> impl Future<Output = Result<String, Self::Error>> + Send;
+
+    /**
+     * Reads the users one row at a time, without loading the whole result
+     * set, and returns the total length of their names.
+     */
+    fn stream(&mut self) -> impl Future<Output = Result<usize, Self::Error>> + Send;
+
+    /**
+     * Fetch all rows of the types table.
+     */
+    fn fetch_types(&mut self)
+    -> impl Future<Output = Result<Vec<Self::Types>, Self::Error>> + Send;
+
+    /**
+     * Insert one row in the types table.
+     */
+    fn insert_types(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Insert one row in the payloads table with `data` as bytea.
+     */
+    fn insert_bytea(&mut self, data: &[u8])
+    -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Fetch the bytea payload created by `setup_payloads` and returns its
+     * length.
+     */
+    fn fetch_bytea(&mut self) -> impl Future<Output = Result<usize, Self::Error>> + Send;
+
+    /**
+     * Insert one row in the payloads table with `content` as text.
+     */
+    fn insert_text(
+        &mut self,
+        content: &str,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Fetch the text payload created by `setup_payloads` and returns its
+     * length.
+     */
+    fn fetch_text(&mut self) -> impl Future<Output = Result<usize, Self::Error>> + Send;
+
+    /**
+     * Pagination strategies of [`AsyncClient::paginate`] the client API can
+     * express.
+     */
+    fn paginations() -> &'static [Pagination];
+
+    /**
+     * Fetch the [`PAGE_SIZE`] users of `page`, numbered from 1, by id order.
+     * The keyset pagination starts after [`page_key`].
+     */
+    fn paginate(
+        &mut self,
+        pagination: Pagination,
+        page: usize,
+    ) -> impl Future<Output = Result<Vec<Self::User>, Self::Error>> + Send;
+
+    /**
+     * Implementations of [`AsyncClient::insert_many`], a loop on
+     * [`AsyncClient::insert_user`] by default.
+     */
+    fn insert_many_implementations() -> &'static [Implementation] {
+        &[Implementation::LOOP]
+    }
+
+    /**
+     * Insert `n` users with `implementation`, one of
+     * [`AsyncClient::insert_many_implementations`].
+     */
+    fn insert_many(
+        &mut self,
+        implementation: &str,
+        n: usize,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        debug_assert_eq!(implementation, Implementation::LOOP.name);
+
+        self.insert_users(n)
+    }
+
+    /**
+     * Implementations of [`AsyncClient::pipeline`], [`AsyncClient::serial`]
+     * by default.
+     */
+    fn pipeline_implementations() -> &'static [Implementation] {
+        &[Implementation::SERIAL]
+    }
+
+    /**
+     * Insert [`PIPELINE_SIZE`] users, then look up as many times the name of
+     * the user identified by [`UUID`], with `implementation`, one of
+     * [`AsyncClient::pipeline_implementations`]. Returns the length of the
+     * names.
+     */
+    fn pipeline(
+        &mut self,
+        implementation: &str,
+    ) -> impl Future<Output = Result<usize, Self::Error>> + Send {
+        debug_assert_eq!(implementation, Implementation::SERIAL.name);
+
+        self.serial()
+    }
+
+    /**
+     * The work of [`AsyncClient::pipeline`] through the other calls of this
+     * trait, each one waiting for its reply.
+     */
+    fn serial(&mut self) -> impl Future<Output = Result<usize, Self::Error>> + Send {
+        async move {
+            for _ in 0..PIPELINE_SIZE {
+                self.insert_user().await?;
+            }
+
+            let mut len = 0;
+
+            for _ in 0..PIPELINE_SIZE {
+                len += self.lookup(Mode::Unprepared).await?.len();
+            }
+
+            Ok(len)
+        }
+    }
+
+    /**
+     * Formats of [`AsyncClient::copy_in`] the client API can express.
+     */
+    fn copy_in_formats() -> &'static [CopyFormat];
+
+    /**
+     * Insert `n` users with a `COPY FROM STDIN` in `format`.
+     */
+    fn copy_in(
+        &mut self,
+        format: CopyFormat,
+        n: usize,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Formats of [`AsyncClient::copy_out`] the client API can express.
+     */
+    fn copy_out_formats() -> &'static [CopyFormat];
+
+    /**
+     * Reads the users table with a `COPY TO STDOUT` in `format` and returns
+     * the number of bytes received.
+     */
+    fn copy_out(
+        &mut self,
+        format: CopyFormat,
+    ) -> impl Future<Output = Result<usize, Self::Error>> + Send;
+
+    /**
+     * Loads the generated users and posts, see [`Client::load`].
+     */
+    fn load(
+        &mut self,
+        users: &[dataset::User],
+        posts: &[dataset::Post],
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /**
+     * Connection of a concurrency benchmark task, see [`Client::worker`].
+     */
+    fn worker(&self, dsn: &str) -> impl Future<Output = Result<Self, Self::Error>> + Send {
+        Self::create(dsn)
+    }
+
+    /**
+     * Same as [`Client::setup`].
+     */
+    fn setup(
+        n: usize,
+    ) -> impl Future<Output = Result<fixture::AsyncFixture<Self>, Self::Error>> + Send {
+        Self::setup_with_posts(n, POSTS)
+    }
+
+    /**
+     * Same as [`AsyncClient::setup`] with `posts` posts per user.
+     */
+    fn setup_with_posts(
+        n: usize,
+        posts: usize,
+    ) -> impl Future<Output = Result<fixture::AsyncFixture<Self>, Self::Error>> + Send {
+        fixture::AsyncFixture::create(
+            include_str!("sql/structure.sql"),
+            Some(dataset::Dataset::new(n, posts)),
+        )
+    }
+
+    /**
+     * Creates the types table, which covers the data types beyond the users
+     * and posts ones, with `n` rows.
+     */
+    fn setup_types(
+        n: usize,
+    ) -> impl Future<Output = Result<fixture::AsyncFixture<Self>, Self::Error>> + Send {
+        async move {
+            fixture::AsyncFixture::create(&format!(include_str!("sql/types.sql"), n), None).await
+        }
+    }
+
+    /**
+     * Creates the payloads table, with one row of a `size` bytes bytea and
+     * text.
+     */
+    fn setup_payloads(
+        size: usize,
+    ) -> impl Future<Output = Result<fixture::AsyncFixture<Self>, Self::Error>> + Send {
+        async move {
+            fixture::AsyncFixture::create(
+                &format!(include_str!("sql/payloads.sql"), size = size),
+                None,
+            )
+            .await
+        }
+    }
+
+    /**
+     * Creates the users table with `n` users, without posts, see
+     * [`Client::setup_keyed`].
+     */
+    fn setup_keyed(
+        n: usize,
+    ) -> impl Future<Output = Result<fixture::AsyncFixture<Self>, Self::Error>> + Send {
+        fixture::AsyncFixture::create(
+            include_str!("sql/structure.sql"),
+            Some(dataset::Dataset::keyed(n)),
+        )
+    }
+
+    /**
+     * Insert `n` users one at a time.
+     */
+    fn insert_users(&mut self, n: usize) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async move {
+            for _ in 0..n {
+                self.insert_user().await?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /**
+     * Creates a user with `posts` posts and returns its id, see
+     * [`Client::create_user`].
+     */
+    fn create_user(
+        &mut self,
+        posts: usize,
+    ) -> impl Future<Output = Result<uuid::Uuid, Self::Error>> + Send {
+        async move {
+            let id = uuid::Uuid::from_u128(fastrand::u128(..));
+
+            self.exec(&format!(
+                include_str!("sql/user.sql"),
+                id = id,
+                posts = posts
+            ))
+            .await?;
+
+            Ok(id)
+        }
+    }
+}
+
+/**
+ * Installs the [`allocator::Counting`] global allocator when the `alloc`
+ * feature is enabled.
+ */
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! global_allocator {
+    () => {
+        #[global_allocator]
+        static ALLOCATOR: $crate::allocator::Counting = $crate::allocator::Counting;
+    };
+}
+
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! global_allocator {
+    () => {};
+}
+
+/**
+ * Generates the bench binary of a client, named after its crate or `$client`
+ * when a crate benchmarks several variants of its client.
+ */
+#[macro_export]
+macro_rules! bench {
+    ($ty:ty) => {
+        $crate::bench!($ty, env!("CARGO_PKG_NAME"));
+    };
+    ($ty:ty, $client:expr) => {
+        use $crate::Client;
+
+        $crate::global_allocator!();
+
+        fn fetch_all(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.fetch_all().unwrap());
+
+            Ok(())
+        }
+
+        fn fetch_first(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.fetch_first().unwrap());
+
+            Ok(())
+        }
+
+        fn fetch_last(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.fetch_last($crate::rows(n)).unwrap());
+
+            Ok(())
+        }
+
+        fn insert_one(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+            let inserted = std::cell::Cell::new(false);
+
+            // The inputs of a batch are all prepared before running it: the
+            // first one deletes the users inserted by the previous batch.
+            b.iter_with_setup(
+                &mut client,
+                |client| {
+                    if inserted.replace(false) {
+                        client.exec($crate::DELETE_INSERTED).unwrap();
+                    }
+                },
+                |client, ()| {
+                    inserted.set(true);
+                    client.insert_user().unwrap()
+                },
+            );
+
+            Ok(())
+        }
+
+        fn insert_many(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            implementation: &str,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+            let inserted = std::cell::Cell::new(false);
+
+            b.iter_with_setup(
+                &mut client,
+                |client| {
+                    if inserted.replace(false) {
+                        client.exec($crate::DELETE_INSERTED).unwrap();
+                    }
+                },
+                |client, ()| {
+                    inserted.set(true);
+                    client.insert_many(implementation, n).unwrap()
+                },
+            );
+
+            Ok(())
+        }
+
+        fn one_relation(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.one_relation().unwrap());
+
+            Ok(())
+        }
+
+        fn all_relations(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.all_relations().unwrap());
+
+            Ok(())
+        }
+
+        fn update_one(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.update_user().unwrap());
+
+            Ok(())
+        }
+
+        fn update_many(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.update_users().unwrap());
+
+            Ok(())
+        }
+
+        fn upsert_one(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_keyed(n)?;
+            let mut i = 0;
+
+            b.iter_with_setup(
+                &mut client,
+                |_| {
+                    i += 1;
+                    $crate::upsert_key(n, i)
+                },
+                |client, id| client.upsert_user(id).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn upsert_many(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_keyed(n)?;
+
+            b.iter_with_setup(
+                &mut client,
+                |_| $crate::upsert_keys(n),
+                |client, ids| client.upsert_users(&ids).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn paginate(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            pagination: $crate::Pagination,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_keyed(n)?;
+            let page = $crate::page(n);
+
+            b.iter(|| client.paginate(pagination, page).unwrap());
+
+            Ok(())
+        }
+
+        fn delete_one(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter_with_setup(
+                &mut client,
+                |client| client.create_user(0).unwrap(),
+                |client, id| client.delete_user(id).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn delete_cascade(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter_with_setup(
+                &mut client,
+                |client| client.create_user($crate::POSTS).unwrap(),
+                |client, id| client.delete_user(id).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn transaction_commit(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.transaction().unwrap());
+
+            Ok(())
+        }
+
+        fn transaction_rollback(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.rollback().unwrap());
+
+            Ok(())
+        }
+
+        fn savepoints(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.savepoints().unwrap());
+
+            Ok(())
+        }
+
+        fn lookup(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            mode: $crate::Mode,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.lookup(mode).unwrap());
+
+            Ok(())
+        }
+
+        fn stream(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_with_posts(n, 0)?;
+
+            b.iter(|| client.stream().unwrap());
+
+            Ok(())
+        }
+
+        fn fetch_types(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_types(n)?;
+
+            b.iter(|| client.fetch_types().unwrap());
+
+            Ok(())
+        }
+
+        fn insert_types(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_types(n)?;
+
+            b.iter(|| client.insert_types().unwrap());
+
+            Ok(())
+        }
+
+        fn copy_in(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            format: $crate::CopyFormat,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.copy_in(format, n).unwrap());
+
+            Ok(())
+        }
+
+        fn copy_out(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            format: $crate::CopyFormat,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_with_posts(n, 0)?;
+
+            b.iter(|| client.copy_out(format).unwrap());
+
+            Ok(())
+        }
+
+        fn insert_bytea(
+            b: &mut $crate::harness::Bencher,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_payloads(size)?;
+            let data = $crate::payload(size);
+
+            b.iter_with_setup(
+                &mut client,
+                |client| client.exec("truncate payloads").unwrap(),
+                |client, ()| client.insert_bytea(data.as_bytes()).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn fetch_bytea(
+            b: &mut $crate::harness::Bencher,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_payloads(size)?;
+
+            b.iter(|| client.fetch_bytea().unwrap());
+
+            Ok(())
+        }
+
+        fn insert_text(
+            b: &mut $crate::harness::Bencher,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_payloads(size)?;
+            let content = $crate::payload(size);
+
+            b.iter_with_setup(
+                &mut client,
+                |client| client.exec("truncate payloads").unwrap(),
+                |client, ()| client.insert_text(&content).unwrap(),
+            );
+
+            Ok(())
+        }
+
+        fn fetch_text(
+            b: &mut $crate::harness::Bencher,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup_payloads(size)?;
+
+            b.iter(|| client.fetch_text().unwrap());
+
+            Ok(())
+        }
+
+        fn pipeline(
+            b: &mut $crate::harness::Bencher,
+            n: usize,
+            implementation: &str,
+        ) -> Result<(), <$ty as $crate::Client>::Error> {
+            let mut client = <$ty as Client>::setup(n)?;
+
+            b.iter(|| client.pipeline(implementation).unwrap());
+
+            Ok(())
+        }
+
+        fn main() {
+            let mut harness = $crate::harness::Harness::from_args($client);
+            let templates = $crate::fixture::Templates::<$ty>::default();
+
+            harness.bench("fetch_all", fetch_all);
+            harness.bench("fetch_first", fetch_first);
+            harness.bench("fetch_last", fetch_last);
+            harness.bench("insert_one", insert_one);
+            for implementation in <$ty as $crate::Client>::insert_many_implementations() {
+                harness.bench_implementation("insert_many", implementation, |b, n| {
+                    insert_many(b, n, implementation.name)
+                });
+            }
+            harness.bench("one_relation", one_relation);
+            harness.bench("all_relations", all_relations);
+            harness.bench("update_one", update_one);
+            harness.bench("update_many", update_many);
+            harness.bench("upsert_one", upsert_one);
+            harness.bench("upsert_many", upsert_many);
+            harness.bench("delete_one", delete_one);
+            harness.bench("delete_cascade", delete_cascade);
+            harness.bench("transaction_commit", transaction_commit);
+            harness.bench("transaction_rollback", transaction_rollback);
+            harness.bench("savepoints", savepoints);
+
+            for mode in <$ty as $crate::Client>::modes() {
+                harness.bench(mode.name(), |b, n| lookup(b, n, *mode));
+            }
+
+            for pagination in <$ty as $crate::Client>::paginations() {
+                harness.bench(pagination.name(), |b, n| paginate(b, n, *pagination));
+            }
+
+            harness.bench_large("stream", stream);
+            harness.bench("fetch_types", fetch_types);
+            harness.bench("insert_types", insert_types);
+            harness.bench_payload("insert_bytea", insert_bytea);
+            harness.bench_payload("fetch_bytea", fetch_bytea);
+            harness.bench_payload("insert_text", insert_text);
+            harness.bench_payload("fetch_text", fetch_text);
+
+            for format in <$ty as $crate::Client>::copy_in_formats() {
+                harness.bench(format.copy_in(), |b, n| copy_in(b, n, *format));
+            }
+
+            for format in <$ty as $crate::Client>::copy_out_formats() {
+                harness.bench(format.copy_out(), |b, n| copy_out(b, n, *format));
+            }
+
+            for implementation in <$ty as $crate::Client>::pipeline_implementations() {
+                harness.bench_implementation("pipeline", implementation, |b, n| {
+                    pipeline(b, n, implementation.name)
+                });
+            }
+
+            harness.bench_concurrency::<$ty>();
+
+            drop(templates);
+            harness.finish();
+        }
+    };
+}
+
+/**
+ * Generates the bench binary of a client, named after its crate or `$client`
+ * when a crate benchmarks several variants of its client.
+ */
+#[macro_export]
+macro_rules! bench_async {
+    ($ty:ty) => {
+        $crate::bench_async!($ty, env!("CARGO_PKG_NAME"));
+    };
+    ($ty:ty, $client:expr) => {
+        use $crate::AsyncClient;
+
+        $crate::global_allocator!();
+
+        async fn fetch_all(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.fetch_all().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn fetch_first(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.fetch_first().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn fetch_last(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.fetch_last($crate::rows(n)).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn insert_one(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+            let inserted = std::cell::Cell::new(false);
+
+            // The inputs of a batch are all prepared before running it: the
+            // first one deletes the users inserted by the previous batch.
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| {
+                    if inserted.replace(false) {
+                        client.exec($crate::DELETE_INSERTED).await.unwrap();
+                    }
+                },
+                async |client, ()| {
+                    inserted.set(true);
+                    client.insert_user().await.unwrap()
+                },
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn insert_many(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            implementation: &str,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+            let inserted = std::cell::Cell::new(false);
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| {
+                    if inserted.replace(false) {
+                        client.exec($crate::DELETE_INSERTED).await.unwrap();
+                    }
+                },
+                async |client, ()| {
+                    inserted.set(true);
+                    client.insert_many(implementation, n).await.unwrap()
+                },
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn one_relation(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.one_relation().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn all_relations(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.all_relations().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn update_one(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.update_user().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn update_many(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.update_users().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn upsert_one(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_keyed(n).await?;
+            let mut i = 0;
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |_| {
+                    i += 1;
+                    $crate::upsert_key(n, i)
+                },
+                async |client, id| client.upsert_user(id).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn upsert_many(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_keyed(n).await?;
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |_| $crate::upsert_keys(n),
+                async |client, ids| client.upsert_users(&ids).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn paginate(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            pagination: $crate::Pagination,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_keyed(n).await?;
+            let page = $crate::page(n);
+
+            b.iter_async(async || client.paginate(pagination, page).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn delete_one(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| client.create_user(0).await.unwrap(),
+                async |client, id| client.delete_user(id).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn delete_cascade(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| client.create_user($crate::POSTS).await.unwrap(),
+                async |client, id| client.delete_user(id).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn transaction_commit(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.transaction().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn transaction_rollback(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.rollback().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn savepoints(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.savepoints().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn lookup(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            mode: $crate::Mode,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.lookup(mode).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn stream(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_with_posts(n, 0).await?;
+
+            b.iter_async(async || client.stream().await.unwrap()).await;
+
+            Ok(())
+        }
+
+        async fn fetch_types(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_types(n).await?;
+
+            b.iter_async(async || client.fetch_types().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn insert_types(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_types(n).await?;
+
+            b.iter_async(async || client.insert_types().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn copy_in(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            format: $crate::CopyFormat,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.copy_in(format, n).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn copy_out(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            format: $crate::CopyFormat,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_with_posts(n, 0).await?;
+
+            b.iter_async(async || client.copy_out(format).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn insert_bytea(
+            b: &mut $crate::harness::Bencher<'_>,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_payloads(size).await?;
+            let data = $crate::payload(size);
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| client.exec("truncate payloads").await.unwrap(),
+                async |client, ()| client.insert_bytea(data.as_bytes()).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn fetch_bytea(
+            b: &mut $crate::harness::Bencher<'_>,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_payloads(size).await?;
+
+            b.iter_async(async || client.fetch_bytea().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn insert_text(
+            b: &mut $crate::harness::Bencher<'_>,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_payloads(size).await?;
+            let content = $crate::payload(size);
+
+            b.iter_with_setup_async(
+                &mut client,
+                async |client| client.exec("truncate payloads").await.unwrap(),
+                async |client, ()| client.insert_text(&content).await.unwrap(),
+            )
+            .await;
+
+            Ok(())
+        }
+
+        async fn fetch_text(
+            b: &mut $crate::harness::Bencher<'_>,
+            size: usize,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup_payloads(size).await?;
+
+            b.iter_async(async || client.fetch_text().await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        async fn pipeline(
+            b: &mut $crate::harness::Bencher<'_>,
+            n: usize,
+            implementation: &str,
+        ) -> Result<(), <$ty as $crate::AsyncClient>::Error> {
+            let mut client = <$ty>::setup(n).await?;
+
+            b.iter_async(async || client.pipeline(implementation).await.unwrap())
+                .await;
+
+            Ok(())
+        }
+
+        fn main() {
+            let mut harness = $crate::harness::Harness::from_args($client);
+            let runtime = $crate::tokio::runtime::Runtime::new().unwrap();
+
+            runtime.block_on(async {
+                let templates = $crate::fixture::AsyncTemplates::<$ty>::default();
+
+                harness.bench_async("fetch_all", fetch_all).await;
+                harness.bench_async("fetch_first", fetch_first).await;
+                harness.bench_async("fetch_last", fetch_last).await;
+                harness.bench_async("insert_one", insert_one).await;
+                for implementation in <$ty as $crate::AsyncClient>::insert_many_implementations() {
+                    harness
+                        .bench_implementation_async("insert_many", implementation, async |b, n| {
+                            insert_many(b, n, implementation.name).await
+                        })
+                        .await;
+                }
+                harness.bench_async("one_relation", one_relation).await;
+                harness.bench_async("all_relations", all_relations).await;
+                harness.bench_async("update_one", update_one).await;
+                harness.bench_async("update_many", update_many).await;
+                harness.bench_async("upsert_one", upsert_one).await;
+                harness.bench_async("upsert_many", upsert_many).await;
+                harness.bench_async("delete_one", delete_one).await;
+                harness.bench_async("delete_cascade", delete_cascade).await;
+                harness
+                    .bench_async("transaction_commit", transaction_commit)
+                    .await;
+                harness
+                    .bench_async("transaction_rollback", transaction_rollback)
+                    .await;
+                harness.bench_async("savepoints", savepoints).await;
+
+                for mode in <$ty as $crate::AsyncClient>::modes() {
+                    harness
+                        .bench_async(mode.name(), async |b, n| lookup(b, n, *mode).await)
+                        .await;
+                }
+
+                for pagination in <$ty as $crate::AsyncClient>::paginations() {
+                    harness
+                        .bench_async(pagination.name(), async |b, n| {
+                            paginate(b, n, *pagination).await
+                        })
+                        .await;
+                }
+
+                harness.bench_large_async("stream", stream).await;
+                harness.bench_async("fetch_types", fetch_types).await;
+                harness.bench_async("insert_types", insert_types).await;
+                harness
+                    .bench_payload_async("insert_bytea", insert_bytea)
+                    .await;
+                harness
+                    .bench_payload_async("fetch_bytea", fetch_bytea)
+                    .await;
+                harness
+                    .bench_payload_async("insert_text", insert_text)
+                    .await;
+                harness.bench_payload_async("fetch_text", fetch_text).await;
+
+                for format in <$ty as $crate::AsyncClient>::copy_in_formats() {
+                    harness
+                        .bench_async(format.copy_in(), async |b, n| copy_in(b, n, *format).await)
+                        .await;
+                }
+
+                for format in <$ty as $crate::AsyncClient>::copy_out_formats() {
+                    harness
+                        .bench_async(format.copy_out(), async |b, n| {
+                            copy_out(b, n, *format).await
+                        })
+                        .await;
+                }
+
+                for implementation in <$ty as $crate::AsyncClient>::pipeline_implementations() {
+                    harness
+                        .bench_implementation_async("pipeline", implementation, async |b, n| {
+                            pipeline(b, n, implementation.name).await
+                        })
+                        .await;
+                }
+
+                harness.bench_concurrency_async::<$ty>().await;
+
+                drop(templates);
+            });
 
-            client.tear_down()
+            harness.finish();
         }
     };
 }
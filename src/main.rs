@@ -0,0 +1,263 @@
+mod chart;
+
+use clap::Parser;
+use elephantry_benchmark::cluster::{self, Cluster};
+use elephantry_benchmark::compare::Comparison;
+use elephantry_benchmark::results::Results;
+use std::path::{Path, PathBuf};
+
+const CLIENTS: [&str; 7] = [
+    "diesel",
+    "elephantry",
+    "libpq",
+    "libpq-binary",
+    "postgres",
+    "sqlx",
+    "tokio-postgres",
+];
+
+/**
+ * Runs the benchmark of each client and merges their results.
+ */
+#[derive(clap::Parser)]
+struct Opt {
+    /// Clients to benchmark (all by default).
+    #[arg(long, value_delimiter = ',', value_parser = clap::builder::PossibleValuesParser::new(CLIENTS))]
+    clients: Vec<String>,
+    /// Operations to run (all by default).
+    #[arg(long, value_delimiter = ',')]
+    operations: Vec<String>,
+    /// JSON results file.
+    #[arg(long, default_value = "results/results.json")]
+    output: PathBuf,
+    /// Also exports the summary statistics in this CSV file.
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Renders the charts, as SVG and PNG, at this path (`results/graph`).
+    #[arg(long)]
+    chart: Option<PathBuf>,
+    /// Uses a logarithmic scale for the durations axis of the charts.
+    #[arg(long)]
+    log_scale: bool,
+    /// Charts durations relative to the fastest client of each benchmark.
+    #[arg(long)]
+    relative: bool,
+    /// Reads the results from this JSON file instead of running the
+    /// benchmarks.
+    #[arg(long, conflicts_with_all = ["clients", "operations"])]
+    input: Option<PathBuf>,
+    /// Saves the results as this named baseline.
+    #[arg(long)]
+    save_baseline: Option<String>,
+    /// Compares the results with this named baseline.
+    #[arg(long)]
+    baseline: Option<String>,
+    /// Directory of the baselines.
+    #[arg(long, default_value = "results/baselines")]
+    baselines: PathBuf,
+    /// Fails when a benchmark is slower than the baseline by more than this
+    /// percentage.
+    #[arg(long, default_value_t = 5.)]
+    threshold: f64,
+    /// Significance level of the comparison with the baseline.
+    #[arg(long, default_value_t = 0.05)]
+    significance_level: f64,
+    /// Counts the allocations of each benchmark iteration.
+    #[arg(long)]
+    allocations: bool,
+    /// Arguments given to the bench binaries (`-- --sizes 1,100`).
+    #[arg(last = true)]
+    args: Vec<String>,
+}
+
+fn main() {
+    let opt = Opt::parse();
+
+    let mut failed = false;
+
+    let results = if let Some(input) = &opt.input {
+        match Results::load(input) {
+            Ok(results) => results,
+            Err(err) => {
+                eprintln!("Unable to read {}: {err}", input.display());
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let clients = if opt.clients.is_empty() {
+            CLIENTS.iter().map(ToString::to_string).collect()
+        } else {
+            opt.clients.clone()
+        };
+
+        // Shared by the bench binaries, through `DATABASE_URL`.
+        let cluster = match Cluster::start_unless_configured() {
+            Ok(cluster) => cluster,
+            Err(err) => {
+                eprintln!("Unable to start a PostgreSQL cluster: {err}");
+                std::process::exit(1);
+            }
+        };
+
+        let mut records = Vec::new();
+
+        for client in &clients {
+            match bench(client, &opt) {
+                Ok(results) => records.extend(results.records),
+                Err(err) => {
+                    eprintln!("{client}: {err}");
+                    failed = true;
+                }
+            }
+        }
+
+        drop(cluster);
+
+        let results = Results::new(records);
+
+        if let Err(err) = save(&results, &opt) {
+            eprintln!("Unable to save results: {err}");
+            failed = true;
+        }
+
+        results
+    };
+
+    if let Some(path) = &opt.chart {
+        let options = chart::Options {
+            log_scale: opt.log_scale,
+            relative: opt.relative,
+        };
+
+        if let Err(err) = chart::render(&results, path, &options) {
+            eprintln!("Unable to render charts: {err}");
+            failed = true;
+        }
+    }
+
+    if let Some(name) = &opt.baseline {
+        match compare(&results, name, &opt) {
+            Ok(comparison) => {
+                comparison.report(name);
+
+                let regressions = comparison.regressions();
+
+                if regressions > 0 {
+                    eprintln!(
+                        "{regressions} benchmark(s) regressed by more than {}%",
+                        opt.threshold
+                    );
+                    failed = true;
+                }
+            }
+            Err(err) => {
+                eprintln!("Unable to read the {name} baseline: {err}");
+                failed = true;
+            }
+        }
+    }
+
+    if let Some(name) = &opt.save_baseline
+        && let Err(err) = save_baseline(&results, name, &opt)
+    {
+        eprintln!("Unable to save the {name} baseline: {err}");
+        failed = true;
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/**
+ * Directory and bench target of a client, the variants of a client are
+ * other bench targets of its crate.
+ */
+fn target(client: &str) -> (&str, &str) {
+    match client {
+        "libpq-binary" => ("libpq", "binary"),
+        _ => (client, "bench"),
+    }
+}
+
+/**
+ * Runs `cargo bench` for one client, the bench binary writes its results in
+ * a temporary file.
+ */
+fn bench(client: &str, opt: &Opt) -> std::io::Result<Results> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = std::env::temp_dir().join(format!(
+        "elephantry-benchmark-{client}-{}.json",
+        std::process::id()
+    ));
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let (dir, target) = target(client);
+
+    let mut command = std::process::Command::new(cargo);
+    command
+        .arg("bench")
+        .arg("--manifest-path")
+        .arg(root.join(dir).join("Cargo.toml"))
+        .arg("--bench")
+        .arg(target);
+
+    if opt.allocations {
+        command.arg("--features").arg("elephantry-benchmark/alloc");
+    }
+
+    if let Some(dsn) = cluster::dsn() {
+        command.env("DATABASE_URL", dsn);
+    }
+
+    command.arg("--").arg("--output").arg(&output);
+
+    if !opt.operations.is_empty() {
+        command.arg("--operations").arg(opt.operations.join(","));
+    }
+
+    let status = command.args(&opt.args).status()?;
+    let results = Results::load(&output);
+
+    std::fs::remove_file(&output).ok();
+
+    if !status.success() {
+        return Err(std::io::Error::other(format!("bench failed ({status})")));
+    }
+
+    results
+}
+
+fn save(results: &Results, opt: &Opt) -> std::io::Result<()> {
+    if let Some(parent) = opt.output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    results.save(&opt.output)?;
+
+    if let Some(csv) = &opt.csv {
+        results.save_csv(csv)?;
+    }
+
+    Ok(())
+}
+
+fn baseline_path(name: &str, opt: &Opt) -> PathBuf {
+    opt.baselines.join(format!("{name}.json"))
+}
+
+fn compare(results: &Results, name: &str, opt: &Opt) -> std::io::Result<Comparison> {
+    let baseline = Results::load(&baseline_path(name, opt))?;
+
+    Ok(Comparison::new(
+        &baseline,
+        results,
+        opt.threshold / 100.,
+        opt.significance_level,
+    ))
+}
+
+fn save_baseline(results: &Results, name: &str, opt: &Opt) -> std::io::Result<()> {
+    std::fs::create_dir_all(&opt.baselines)?;
+
+    results.save(&baseline_path(name, opt))
+}
@@ -0,0 +1,225 @@
+use crate::allocator::Allocations;
+use crate::stats::Stats;
+
+/**
+ * Version of the results file format, incremented on each incompatible
+ * change: 2 names the benchmarks of each implementation of an operation
+ * (`insert_many/loop/100`, was `insert_many/100`).
+ */
+pub const VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Results {
+    pub version: u32,
+    pub records: Vec<Record>,
+}
+
+/**
+ * Result of one operation of one client on one dataset size.
+ */
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Record {
+    pub client: String,
+    pub operation: String,
+    pub size: usize,
+    /**
+     * Per-iteration duration of each sample, in nanoseconds.
+     */
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /**
+     * Allocations of one iteration, when the bench binary counts them.
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<Allocations>,
+    /**
+     * Set for the concurrency benchmarks, the samples are then the latency
+     * of each call.
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<Concurrency>,
+    /**
+     * Implementation of the operation, set for the operations benchmarked
+     * once per implementation (`insert_many`, `pipeline`), even when the
+     * client has only one.
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<Variant>,
+}
+
+impl Record {
+    /**
+     * Benchmark name: `operation/size`, or `operation/implementation/size`
+     * (see [`Record::benchmark`]), followed by the number of workers for
+     * concurrency benchmarks.
+     */
+    pub fn name(&self) -> String {
+        match &self.concurrency {
+            Some(concurrency) => {
+                format!("{}/{}/{}", self.benchmark(), self.size, concurrency.threads)
+            }
+            None => format!("{}/{}", self.benchmark(), self.size),
+        }
+    }
+
+    /**
+     * The operation, followed by the name of its implementation if any:
+     * `insert_many/copy`.
+     */
+    pub fn benchmark(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{}/{}", self.operation, variant.name),
+            None => self.operation.clone(),
+        }
+    }
+
+    /**
+     * Label of the client in the charts, with the implementation and its
+     * style: `diesel copy (optimized)`.
+     */
+    pub fn series(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!(
+                "{} {} ({})",
+                self.client,
+                variant.name,
+                variant.style.name()
+            ),
+            None => self.client.clone(),
+        }
+    }
+}
+
+/**
+ * One of the implementations of an operation by a client.
+ */
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Variant {
+    pub name: String,
+    pub style: Style,
+}
+
+/**
+ * Whether an implementation is the straightforward use of the client API or
+ * trades it for speed.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /**
+     * What the client documentation suggests.
+     */
+    Idiomatic,
+    /**
+     * The fastest way the client API allows.
+     */
+    Optimized,
+}
+
+impl Style {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Idiomatic => "idiomatic",
+            Self::Optimized => "optimized",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Concurrency {
+    /**
+     * Number of worker threads (or tasks for async clients).
+     */
+    pub threads: usize,
+    /**
+     * Calls per second, all workers together.
+     */
+    pub throughput: f64,
+}
+
+impl Results {
+    pub fn new(records: Vec<Record>) -> Self {
+        Self {
+            version: VERSION,
+            records,
+        }
+    }
+
+    pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let results: Self = serde_json::from_reader(std::io::BufReader::new(file))?;
+
+        if results.version != VERSION {
+            return Err(std::io::Error::other(format!(
+                "{}: unsupported results version {} (expected {VERSION})",
+                path.display(),
+                results.version
+            )));
+        }
+
+        Ok(results)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), self)?;
+
+        Ok(())
+    }
+
+    /**
+     * Exports the summary statistics of each record, without the samples.
+     */
+    pub fn save_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+
+        writeln!(
+            file,
+            "client,operation,size,samples,mean,std_dev,median,median_lower,median_upper,min,max,p5,p25,p75,p95,p99,outliers,allocations,allocated_bytes,peak_bytes,threads,throughput,variant,style"
+        )?;
+
+        for record in &self.records {
+            let stats = &record.stats;
+            let allocations = record
+                .allocations
+                .map(|x| format!("{},{},{}", x.count, x.bytes, x.peak))
+                .unwrap_or_else(|| ",,".to_string());
+            let concurrency = record
+                .concurrency
+                .map(|x| format!("{},{}", x.threads, x.throughput))
+                .unwrap_or_else(|| ",".to_string());
+            let variant = record
+                .variant
+                .as_ref()
+                .map(|x| format!("{},{}", x.name, x.style.name()))
+                .unwrap_or_else(|| ",".to_string());
+
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{allocations},{concurrency},{variant}",
+                record.client,
+                record.operation,
+                record.size,
+                record.samples.len(),
+                stats.mean,
+                stats.std_dev,
+                stats.median,
+                stats.median_ci.lower,
+                stats.median_ci.upper,
+                stats.min,
+                stats.max,
+                stats.p5,
+                stats.p25,
+                stats.p75,
+                stats.p95,
+                stats.p99,
+                stats.outliers.total()
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+begin;
+
+create table payloads (
+    id serial primary key,
+    data bytea not null default '',
+    content text not null default ''
+);
+
+-- Stored uncompressed, so the server doesn't spend its time in pglz.
+alter table payloads alter column data set storage external,
+    alter column content set storage external;
+
+insert into payloads (data, content)
+    select convert_to(payload, 'UTF8'), payload
+        from left(repeat('0123456789abcdef', {size} / 16 + 1), {size}) as payload;
+
+commit;
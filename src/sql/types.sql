@@ -0,0 +1,32 @@
+begin;
+
+create type mood as enum ('Sad', 'Ok', 'Happy');
+
+create table types (
+    id serial primary key,
+    document jsonb not null,
+    amount numeric not null,
+    data bytea not null,
+    integers int4[] not null,
+    floats float8[] not null,
+    created_at timestamptz not null,
+    duration interval not null,
+    address inet not null,
+    mood mood not null,
+    period int4range not null
+);
+
+insert into types (document, amount, data, integers, floats, created_at, duration, address, mood, period)
+    select jsonb_build_object('id', id, 'name', concat('Name ', id), 'tags', jsonb_build_array('a', 'b')),
+        id * 1.25,
+        decode(md5(id::text), 'hex'),
+        array[id, id + 1, id + 2],
+        array[id * 0.5, id * 1.5]::float8[],
+        '2024-01-01 00:00:00+00'::timestamptz + id * interval '1 second',
+        make_interval(months => id % 12, days => id % 30, secs => id % 60),
+        '10.0.0.0'::inet + id,
+        (enum_range(null::mood))[id % 3 + 1],
+        int4range(id, id + 10)
+        from generate_series(1, {}) as id;
+
+commit;
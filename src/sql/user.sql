@@ -0,0 +1,5 @@
+insert into users (id, name, hair_color) values ('{id}', 'User', 'hair color');
+
+insert into posts (title, content, author)
+    select concat('Post number ', id), 'abc', '{id}'
+        from generate_series(1, {posts}) as id;
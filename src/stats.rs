@@ -0,0 +1,438 @@
+/**
+ * Summary statistics of a benchmark, computed from the per-iteration
+ * duration (in nanoseconds) of each sample.
+ */
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub mean_ci: Interval,
+    pub median_ci: Interval,
+    pub outliers: Outliers,
+}
+
+impl Stats {
+    pub fn new(samples: &[f64], confidence_level: f64, resamples: usize) -> Self {
+        assert!(
+            !samples.is_empty(),
+            "cannot compute statistics without sample"
+        );
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let mean = mean(samples);
+
+        Self {
+            mean,
+            std_dev: std_dev(samples, mean),
+            median: percentile(&sorted, 50.),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p5: percentile(&sorted, 5.),
+            p25: percentile(&sorted, 25.),
+            p75: percentile(&sorted, 75.),
+            p95: percentile(&sorted, 95.),
+            p99: percentile(&sorted, 99.),
+            mean_ci: bootstrap(samples, confidence_level, resamples, mean_of),
+            median_ci: bootstrap(samples, confidence_level, resamples, median_of),
+            outliers: Outliers::classify(&sorted),
+        }
+    }
+}
+
+/**
+ * Confidence interval of an estimate.
+ */
+#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/**
+ * Outliers classification using the Tukey's fences: a sample is a mild
+ * outlier outside of 1.5 IQR and a severe one outside of 3 IQR.
+ */
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Outliers {
+    pub low_severe: usize,
+    pub low_mild: usize,
+    pub high_mild: usize,
+    pub high_severe: usize,
+}
+
+impl Outliers {
+    fn classify(sorted: &[f64]) -> Self {
+        let q1 = percentile(sorted, 25.);
+        let q3 = percentile(sorted, 75.);
+        let iqr = q3 - q1;
+
+        let mut outliers = Self::default();
+
+        for &x in sorted {
+            if x < q1 - 3. * iqr {
+                outliers.low_severe += 1;
+            } else if x < q1 - 1.5 * iqr {
+                outliers.low_mild += 1;
+            } else if x > q3 + 3. * iqr {
+                outliers.high_severe += 1;
+            } else if x > q3 + 1.5 * iqr {
+                outliers.high_mild += 1;
+            }
+        }
+
+        outliers
+    }
+
+    pub fn total(&self) -> usize {
+        self.low_severe + self.low_mild + self.high_mild + self.high_severe
+    }
+}
+
+pub fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/**
+ * Sample standard deviation.
+ */
+pub fn std_dev(samples: &[f64], mean: f64) -> f64 {
+    if samples.len() < 2 {
+        return 0.;
+    }
+
+    let sum = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+
+    (sum / (samples.len() - 1) as f64).sqrt()
+}
+
+/**
+ * Percentile of an already sorted slice, with linear interpolation between
+ * the closest ranks.
+ */
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100. * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn mean_of(samples: &mut [f64]) -> f64 {
+    mean(samples)
+}
+
+fn median_of(samples: &mut [f64]) -> f64 {
+    samples.sort_by(f64::total_cmp);
+
+    percentile(samples, 50.)
+}
+
+/**
+ * Percentile bootstrap of `estimator`. The generator is seeded, so the same
+ * samples always give the same interval.
+ */
+fn bootstrap(
+    samples: &[f64],
+    confidence_level: f64,
+    resamples: usize,
+    estimator: fn(&mut [f64]) -> f64,
+) -> Interval {
+    let mut rng = fastrand::Rng::with_seed(samples.len() as u64);
+    let mut resample = vec![0.; samples.len()];
+    let mut estimates = Vec::with_capacity(resamples);
+
+    for _ in 0..resamples.max(1) {
+        for x in resample.iter_mut() {
+            *x = samples[rng.usize(..samples.len())];
+        }
+
+        estimates.push(estimator(&mut resample));
+    }
+
+    estimates.sort_by(f64::total_cmp);
+
+    let alpha = (1. - confidence_level) / 2.;
+
+    Interval {
+        lower: percentile(&estimates, alpha * 100.),
+        upper: percentile(&estimates, (1. - alpha) * 100.),
+    }
+}
+
+/**
+ * Linear model `duration = overhead + per_row × rows`.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct Fit {
+    pub overhead: f64,
+    pub per_row: f64,
+    pub r_squared: f64,
+}
+
+/**
+ * Fits a linear model on `(rows, duration)` points. Points are weighted by
+ * the inverse of their squared duration: sizes often span several orders of
+ * magnitude and the relative error is minimized, otherwise the largest size
+ * alone decides of the overhead.
+ */
+pub fn fit(points: &[(f64, f64)]) -> Option<Fit> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let weights = points.iter().map(|(_, y)| 1. / (y * y)).collect::<Vec<_>>();
+    let sum = weights.iter().sum::<f64>();
+    let mean_x = points
+        .iter()
+        .zip(&weights)
+        .map(|((x, _), w)| w * x)
+        .sum::<f64>()
+        / sum;
+    let mean_y = points
+        .iter()
+        .zip(&weights)
+        .map(|((_, y), w)| w * y)
+        .sum::<f64>()
+        / sum;
+
+    let mut sxx = 0.;
+    let mut sxy = 0.;
+    let mut syy = 0.;
+
+    for ((x, y), w) in points.iter().zip(&weights) {
+        sxx += w * (x - mean_x).powi(2);
+        sxy += w * (x - mean_x) * (y - mean_y);
+        syy += w * (y - mean_y).powi(2);
+    }
+
+    if sxx == 0. {
+        return None;
+    }
+
+    let per_row = sxy / sxx;
+    let r_squared = if syy == 0. {
+        1.
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+
+    Some(Fit {
+        overhead: mean_y - per_row * mean_x,
+        per_row,
+        r_squared,
+    })
+}
+
+/**
+ * Formats a duration in nanoseconds with the most readable unit.
+ */
+pub fn format_duration(ns: f64) -> String {
+    if ns.abs() < 1_000. {
+        format!("{ns:.2} ns")
+    } else if ns.abs() < 1_000_000. {
+        format!("{:.2} µs", ns / 1_000.)
+    } else if ns.abs() < 1_000_000_000. {
+        format!("{:.2} ms", ns / 1_000_000.)
+    } else {
+        format!("{:.2} s", ns / 1_000_000_000.)
+    }
+}
+
+/**
+ * Two-sided p-value of the Mann-Whitney U test: the probability to observe
+ * such a difference between the `a` and `b` samples if they come from the
+ * same distribution. Durations are skewed and have outliers, a rank test
+ * doesn't assume they are normally distributed.
+ */
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 1.;
+    }
+
+    let mut values = a
+        .iter()
+        .map(|x| (*x, true))
+        .chain(b.iter().map(|x| (*x, false)))
+        .collect::<Vec<_>>();
+    values.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let n = values.len() as f64;
+    let mut rank_sum = 0.;
+    let mut ties = 0.;
+    let mut i = 0;
+
+    while i < values.len() {
+        let mut j = i;
+        while j + 1 < values.len() && values[j + 1].0 == values[i].0 {
+            j += 1;
+        }
+
+        // Tied values share the average of their ranks.
+        let rank = (i + j) as f64 / 2. + 1.;
+        let count = (j - i + 1) as f64;
+
+        rank_sum += rank * values[i..=j].iter().filter(|x| x.1).count() as f64;
+        ties += count.powi(3) - count;
+
+        i = j + 1;
+    }
+
+    let n1 = a.len() as f64;
+    let n2 = b.len() as f64;
+    let u = rank_sum - n1 * (n1 + 1.) / 2.;
+    let mean = n1 * n2 / 2.;
+    let variance = n1 * n2 / 12. * ((n + 1.) - ties / (n * (n - 1.)));
+
+    if variance <= 0. {
+        return 1.;
+    }
+
+    // Continuity correction.
+    let z = ((u - mean).abs() - 0.5).max(0.) / variance.sqrt();
+
+    (2. * (1. - normal_cdf(z))).clamp(0., 1.)
+}
+
+/**
+ * Cumulative distribution function of the standard normal distribution.
+ */
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1. + erf(z / std::f64::consts::SQRT_2))
+}
+
+/**
+ * Error function, Abramowitz and Stegun approximation 7.1.26 (maximum error
+ * 1.5e-7).
+ */
+fn erf(x: f64) -> f64 {
+    let t = 1. / (1. + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1. - poly * (-x * x).exp();
+
+    y.copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn percentile() {
+        let sorted = [1., 2., 3., 4., 5.];
+
+        assert_eq!(super::percentile(&sorted, 0.), 1.);
+        assert_eq!(super::percentile(&sorted, 50.), 3.);
+        assert_eq!(super::percentile(&sorted, 100.), 5.);
+        assert_eq!(super::percentile(&sorted, 25.), 2.);
+        assert!((super::percentile(&sorted, 10.) - 1.4).abs() < 1e-12);
+        assert!((super::percentile(&sorted, 99.) - 4.96).abs() < 1e-12);
+
+        assert_eq!(super::percentile(&[10., 20.], 50.), 15.);
+        assert_eq!(super::percentile(&[7.], 0.), 7.);
+        assert_eq!(super::percentile(&[7.], 100.), 7.);
+    }
+
+    #[test]
+    fn outliers() {
+        // q1 = 11.5, q3 = 18.5: mild fences at 1 and 29, severe ones at -9.5
+        // and 39.5.
+        let sorted = [
+            -40., -5., 10., 11., 12., 13., 14., 15., 16., 17., 18., 19., 20., 35., 60.,
+        ];
+        let outliers = super::Outliers::classify(&sorted);
+
+        assert_eq!(outliers.low_severe, 1);
+        assert_eq!(outliers.low_mild, 1);
+        assert_eq!(outliers.high_mild, 1);
+        assert_eq!(outliers.high_severe, 1);
+        assert_eq!(outliers.total(), 4);
+
+        let outliers = super::Outliers::classify(&[1., 2., 3., 4., 5.]);
+
+        assert_eq!(outliers.total(), 0);
+    }
+
+    #[test]
+    fn fit() {
+        let points = [1., 100., 10_000.].map(|x| (x, 1_000. + 5. * x));
+        let fit = super::fit(&points).unwrap();
+
+        assert!((fit.overhead - 1_000.).abs() < 1e-6);
+        assert!((fit.per_row - 5.).abs() < 1e-9);
+        assert!((fit.r_squared - 1.).abs() < 1e-9);
+
+        assert!(super::fit(&points[..1]).is_none());
+        assert!(super::fit(&[(10., 1.), (10., 2.)]).is_none());
+    }
+
+    #[test]
+    fn mann_whitney() {
+        // U = 0, z = 12 / √(25 × 11 / 12): R `wilcox.test(1:5, 6:10, exact =
+        // FALSE)` p-value.
+        let a = [1., 2., 3., 4., 5.];
+        let b = [6., 7., 8., 9., 10.];
+
+        assert!((super::mann_whitney(&a, &b) - 0.012_186).abs() < 1e-5);
+        assert!((super::mann_whitney(&b, &a) - 0.012_186).abs() < 1e-5);
+
+        assert!((super::mann_whitney(&a, &a) - 1.).abs() < 1e-6);
+        assert_eq!(super::mann_whitney(&a, &[]), 1.);
+    }
+
+    #[test]
+    fn mann_whitney_ties() {
+        // Ranks of a: 1, 3, 3, 5.5, so U = 2.5; the tie correction of the
+        // variance is (3³ - 3) + (2³ - 2) = 30.
+        let a = [1., 2., 2., 3.];
+        let b = [2., 3., 4., 5.];
+
+        assert!((super::mann_whitney(&a, &b) - 0.136_658).abs() < 1e-5);
+
+        // Every value tied, the variance is null.
+        assert_eq!(super::mann_whitney(&[1., 1.], &[1., 1.]), 1.);
+    }
+
+    #[test]
+    fn bootstrap_constant() {
+        let samples = [42.; 20];
+
+        for estimator in [super::mean_of, super::median_of] {
+            let interval = super::bootstrap(&samples, 0.95, 1_000, estimator);
+
+            assert_eq!(interval.lower, 42.);
+            assert_eq!(interval.upper, 42.);
+        }
+    }
+
+    #[test]
+    fn bootstrap() {
+        let samples = (1..=100).map(f64::from).collect::<Vec<_>>();
+        let interval = super::bootstrap(&samples, 0.95, 10_000, super::mean_of);
+
+        // Standard error of the mean: 29.0 / √100, the 95% interval is about
+        // 50.5 ± 5.7.
+        assert!(interval.lower > 44. && interval.lower < 50.5);
+        assert!(interval.upper > 50.5 && interval.upper < 57.);
+
+        let narrow = super::bootstrap(&samples, 0.5, 10_000, super::mean_of);
+
+        assert!(narrow.lower > interval.lower);
+        assert!(narrow.upper < interval.upper);
+
+        let again = super::bootstrap(&samples, 0.95, 10_000, super::mean_of);
+
+        assert_eq!(interval.lower, again.lower);
+        assert_eq!(interval.upper, again.upper);
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "postgres")]
+mod postgres;
+
+#[cfg(feature = "postgres")]
+pub use postgres::{Range, Types};
+
+use std::ops::Bound;
+
+/**
+ * Row inserted by the `insert_types` benchmarks, in plain Rust types: each
+ * client maps them to its own.
+ */
+pub struct NewTypes {
+    pub document: serde_json::Value,
+    /// `amount.0 × 10^-amount.1`.
+    pub amount: (i64, u32),
+    pub data: Vec<u8>,
+    pub integers: Vec<i32>,
+    pub floats: Vec<f64>,
+    /// Seconds since the Unix epoch.
+    pub created_at: i64,
+    pub duration: Interval,
+    pub address: std::net::IpAddr,
+    pub mood: Mood,
+    pub period: (Bound<i32>, Bound<i32>),
+}
+
+impl NewTypes {
+    pub fn new() -> Self {
+        Self {
+            document: serde_json::json!({"id": 0, "name": "Name", "tags": ["a", "b"]}),
+            amount: (125, 2),
+            data: (1..=16).collect(),
+            integers: vec![1, 2, 3],
+            floats: vec![0.5, 1.5],
+            created_at: 1_704_067_200,
+            duration: Interval {
+                months: 1,
+                days: 2,
+                microseconds: 3_000_000,
+            },
+            address: std::net::Ipv4Addr::new(10, 0, 0, 1).into(),
+            mood: Mood::Happy,
+            period: (Bound::Included(1), Bound::Excluded(11)),
+        }
+    }
+}
+
+impl Default for NewTypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "postgres",
+    derive(postgres_types::FromSql, postgres_types::ToSql),
+    postgres(name = "mood")
+)]
+pub enum Mood {
+    Sad,
+    Ok,
+    Happy,
+}
+
+impl std::str::FromStr for Mood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Sad" => Ok(Self::Sad),
+            "Ok" => Ok(Self::Ok),
+            "Happy" => Ok(Self::Happy),
+            _ => Err(format!("unknown mood: {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
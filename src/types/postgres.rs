@@ -0,0 +1,179 @@
+use super::{Interval, Mood, NewTypes};
+use postgres_types::{FromSql, IsNull, ToSql, Type, accepts, to_sql_checked};
+use std::ops::Bound;
+
+type Error = Box<dyn std::error::Error + Sync + Send>;
+
+/**
+ * Row of the types table, shared by the clients built on postgres-types
+ * (postgres and tokio-postgres).
+ */
+pub struct Types {
+    pub id: Option<i32>,
+    pub document: serde_json::Value,
+    pub amount: rust_decimal::Decimal,
+    pub data: Vec<u8>,
+    pub integers: Vec<i32>,
+    pub floats: Vec<f64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub duration: Interval,
+    pub address: std::net::IpAddr,
+    pub mood: Mood,
+    pub period: Range,
+}
+
+impl Types {
+    pub fn new() -> Self {
+        NewTypes::new().into()
+    }
+
+    /**
+     * Reads a row of `SELECT * FROM types`, `postgres::Row` is a re-export
+     * of `tokio_postgres::Row`.
+     */
+    pub fn from_row(row: &tokio_postgres::Row) -> Self {
+        Self {
+            id: row.get("id"),
+            document: row.get("document"),
+            amount: row.get("amount"),
+            data: row.get("data"),
+            integers: row.get("integers"),
+            floats: row.get("floats"),
+            created_at: row.get("created_at"),
+            duration: row.get("duration"),
+            address: row.get("address"),
+            mood: row.get("mood"),
+            period: row.get("period"),
+        }
+    }
+}
+
+impl Default for Types {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<NewTypes> for Types {
+    fn from(types: NewTypes) -> Self {
+        Self {
+            id: None,
+            document: types.document,
+            amount: rust_decimal::Decimal::new(types.amount.0, types.amount.1),
+            data: types.data,
+            integers: types.integers,
+            floats: types.floats,
+            created_at: chrono::DateTime::from_timestamp(types.created_at, 0).unwrap(),
+            duration: types.duration,
+            address: types.address,
+            mood: types.mood,
+            period: Range(types.period.0, types.period.1),
+        }
+    }
+}
+
+/**
+ * postgres-types has no interval mapping.
+ */
+impl<'a> FromSql<'a> for Interval {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Error> {
+        Ok(Self {
+            microseconds: i64::from_be_bytes(raw[..8].try_into()?),
+            days: i32::from_be_bytes(raw[8..12].try_into()?),
+            months: i32::from_be_bytes(raw[12..16].try_into()?),
+        })
+    }
+
+    accepts!(INTERVAL);
+}
+
+impl ToSql for Interval {
+    fn to_sql(&self, _: &Type, out: &mut bytes::BytesMut) -> Result<IsNull, Error> {
+        out.extend_from_slice(&self.microseconds.to_be_bytes());
+        out.extend_from_slice(&self.days.to_be_bytes());
+        out.extend_from_slice(&self.months.to_be_bytes());
+
+        Ok(IsNull::No)
+    }
+
+    accepts!(INTERVAL);
+    to_sql_checked!();
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LOWER_INCLUSIVE: u8 = 0x02;
+const RANGE_UPPER_INCLUSIVE: u8 = 0x04;
+const RANGE_LOWER_INFINITE: u8 = 0x08;
+const RANGE_UPPER_INFINITE: u8 = 0x10;
+
+/**
+ * int4range, postgres-types has no range mapping. An empty range is
+ * `(Excluded(0), Excluded(0))`.
+ */
+#[derive(Debug)]
+pub struct Range(pub Bound<i32>, pub Bound<i32>);
+
+impl<'a> FromSql<'a> for Range {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Error> {
+        let flags = raw[0];
+
+        if flags & RANGE_EMPTY != 0 {
+            return Ok(Self(Bound::Excluded(0), Bound::Excluded(0)));
+        }
+
+        let mut offset = 1;
+        let mut bound = |infinite, inclusive| -> Result<Bound<i32>, Error> {
+            if flags & infinite != 0 {
+                return Ok(Bound::Unbounded);
+            }
+
+            let value = i32::from_be_bytes(raw[offset + 4..offset + 8].try_into()?);
+            offset += 8;
+
+            if flags & inclusive != 0 {
+                Ok(Bound::Included(value))
+            } else {
+                Ok(Bound::Excluded(value))
+            }
+        };
+
+        let lower = bound(RANGE_LOWER_INFINITE, RANGE_LOWER_INCLUSIVE)?;
+        let upper = bound(RANGE_UPPER_INFINITE, RANGE_UPPER_INCLUSIVE)?;
+
+        Ok(Self(lower, upper))
+    }
+
+    accepts!(INT4_RANGE);
+}
+
+impl ToSql for Range {
+    fn to_sql(&self, _: &Type, out: &mut bytes::BytesMut) -> Result<IsNull, Error> {
+        let mut flags = 0;
+        let mut values = Vec::new();
+
+        for (bound, infinite, inclusive) in [
+            (self.0, RANGE_LOWER_INFINITE, RANGE_LOWER_INCLUSIVE),
+            (self.1, RANGE_UPPER_INFINITE, RANGE_UPPER_INCLUSIVE),
+        ] {
+            match bound {
+                Bound::Included(value) => {
+                    flags |= inclusive;
+                    values.push(value);
+                }
+                Bound::Excluded(value) => values.push(value),
+                Bound::Unbounded => flags |= infinite,
+            }
+        }
+
+        out.extend_from_slice(&[flags]);
+        for value in values {
+            out.extend_from_slice(&4_i32.to_be_bytes());
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+
+        Ok(IsNull::No)
+    }
+
+    accepts!(INT4_RANGE);
+    to_sql_checked!();
+}
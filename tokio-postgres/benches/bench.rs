@@ -0,0 +1 @@
+elephantry_benchmark::bench_async! {tokio_postgres_bench::Connection}
@@ -0,0 +1,567 @@
+pub use elephantry_benchmark::types::{Interval, Mood, Range, Types};
+
+pub struct User {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub hair_color: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub posts: Option<Vec<Post>>,
+}
+
+impl User {
+    fn from_row(row: &tokio_postgres::Row) -> Self {
+        Self {
+            id: row.get("id"),
+            name: row.get("name"),
+            hair_color: row.get("hair_color"),
+            created_at: row.get("created_at"),
+            posts: row.try_get("posts").ok(),
+        }
+    }
+}
+
+#[derive(Clone, postgres_derive::FromSql)]
+pub struct Post {
+    pub id: Option<uuid::Uuid>,
+    pub title: String,
+    pub content: String,
+}
+
+pub struct Connection(tokio_postgres::Client, Option<tokio_postgres::Statement>);
+
+impl elephantry_benchmark::AsyncClient for Connection {
+    type Error = tokio_postgres::Error;
+    type User = User;
+    type Post = Post;
+    type Types = Types;
+
+    async fn create(dsn: &str) -> Result<Self, Self::Error> {
+        let (client, connection) = tokio_postgres::connect(dsn, tokio_postgres::NoTls).await?;
+
+        tokio::spawn(connection);
+
+        Ok(Self(client, None))
+    }
+
+    async fn exec(&mut self, query: &str) -> Result<(), Self::Error> {
+        self.0.batch_execute(query).await.map(|_| ())
+    }
+
+    async fn insert_user(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+                &[&"User".to_string(), &"hair color".to_string()],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn fetch_all(&mut self) -> Result<Vec<Self::User>, Self::Error> {
+        let results = self
+            .0
+            .query("SELECT id, name, hair_color, created_at FROM users", &[])
+            .await?
+            .iter()
+            .map(User::from_row)
+            .collect::<Vec<_>>();
+
+        Ok(results)
+    }
+
+    async fn fetch_first(&mut self) -> Result<Self::User, Self::Error> {
+        let result = self
+            .0
+            .query("SELECT id, name, hair_color, created_at FROM users", &[])
+            .await?
+            .iter()
+            .map(User::from_row)
+            .next()
+            .unwrap();
+
+        Ok(result)
+    }
+
+    async fn fetch_last(&mut self, rows: usize) -> Result<Self::User, Self::Error> {
+        let result = self
+            .0
+            .query("SELECT id, name, hair_color, created_at FROM users", &[])
+            .await?
+            .iter()
+            .map(User::from_row)
+            .nth(rows - 1)
+            .unwrap();
+
+        Ok(result)
+    }
+
+    async fn one_relation(&mut self) -> Result<(Self::User, Vec<Self::Post>), Self::Error> {
+        let query = r#"
+select u.*, array_agg(p)
+    from users u
+    join posts p on p.author = u.id
+    where u.id = $1
+    group by u.id, u.name, u.hair_color, u.created_at
+"#;
+
+        let user = self
+            .0
+            .query(query, &[&elephantry_benchmark::UUID])
+            .await?
+            .iter()
+            .map(User::from_row)
+            .next()
+            .unwrap();
+        let posts = user.posts.clone().unwrap_or_default();
+
+        Ok((user, posts))
+    }
+
+    async fn all_relations(&mut self) -> Result<Vec<(Self::User, Vec<Self::Post>)>, Self::Error> {
+        let query = r#"
+select u.*, array_agg(p)
+    from users u
+    join posts p on p.author = u.id
+    group by u.id, u.name, u.hair_color, u.created_at
+"#;
+
+        let users = self
+            .0
+            .query(query, &[])
+            .await?
+            .iter()
+            .map(|x| {
+                let user = User::from_row(x);
+                let posts = user.posts.clone().unwrap_or_default();
+
+                (user, posts)
+            })
+            .collect();
+
+        Ok(users)
+    }
+
+    async fn update_user(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "UPDATE users SET name = $2, hair_color = $3 WHERE id = $1",
+                &[&elephantry_benchmark::UUID, &"User", &"hair color"],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn update_users(&mut self) -> Result<(), Self::Error> {
+        self.0
+            .execute("UPDATE users SET hair_color = $1", &[&"hair color"])
+            .await
+            .map(|_| ())
+    }
+
+    async fn delete_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .execute("DELETE FROM users WHERE id = $1", &[&id])
+            .await
+            .map(|_| ())
+    }
+
+    async fn upsert_user(&mut self, id: uuid::Uuid) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "INSERT INTO users (id, name, hair_color) VALUES ($1, $2, $3)
+                    ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+                &[&id, &"User", &"hair color"],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn upsert_users(&mut self, ids: &[uuid::Uuid]) -> Result<(), Self::Error> {
+        self.0
+            .execute(
+                "INSERT INTO users (id, name, hair_color) SELECT id, $2, $3 FROM unnest($1::uuid[]) AS id
+                    ON CONFLICT (id) DO UPDATE SET name = excluded.name, hair_color = excluded.hair_color",
+                &[&ids, &"User", &"hair color"],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn transaction(&mut self) -> Result<(), Self::Error> {
+        let transaction = self.0.transaction().await?;
+
+        transaction
+            .execute(
+                "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+                &[&"User", &"hair color"],
+            )
+            .await?;
+        transaction
+            .execute(
+                "UPDATE users SET hair_color = $2 WHERE id = $1",
+                &[&elephantry_benchmark::UUID, &"hair color"],
+            )
+            .await?;
+
+        transaction.commit().await
+    }
+
+    async fn rollback(&mut self) -> Result<(), Self::Error> {
+        let transaction = self.0.transaction().await?;
+
+        transaction
+            .execute(
+                "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+                &[&"User", &"hair color"],
+            )
+            .await?;
+
+        transaction.rollback().await
+    }
+
+    async fn savepoints(&mut self) -> Result<(), Self::Error> {
+        let query = "INSERT INTO users (name, hair_color) VALUES ($1, $2)";
+
+        let mut transaction = self.0.transaction().await?;
+        transaction
+            .execute(query, &[&"User", &"hair color"])
+            .await?;
+
+        let mut outer = transaction.transaction().await?;
+        outer.execute(query, &[&"User", &"hair color"]).await?;
+
+        let inner = outer.transaction().await?;
+        inner.execute(query, &[&"User", &"hair color"]).await?;
+        inner.rollback().await?;
+
+        outer.commit().await?;
+        transaction.commit().await
+    }
+
+    fn paginations() -> &'static [elephantry_benchmark::Pagination] {
+        &elephantry_benchmark::Pagination::ALL
+    }
+
+    async fn paginate(
+        &mut self,
+        pagination: elephantry_benchmark::Pagination,
+        page: usize,
+    ) -> Result<Vec<Self::User>, Self::Error> {
+        use elephantry_benchmark::{PAGE_SIZE, Pagination};
+
+        let offset = (page - 1) * PAGE_SIZE;
+
+        let rows = match pagination {
+            Pagination::Offset => {
+                self.0
+                    .query(
+                        "SELECT id, name, hair_color, created_at FROM users ORDER BY id LIMIT $1 OFFSET $2",
+                        &[&(PAGE_SIZE as i64), &(offset as i64)],
+                    )
+                    .await?
+            }
+            Pagination::Keyset => {
+                self.0
+                    .query(
+                        "SELECT id, name, hair_color, created_at FROM users WHERE id > $1 ORDER BY id LIMIT $2",
+                        &[&elephantry_benchmark::page_key(page), &(PAGE_SIZE as i64)],
+                    )
+                    .await?
+            }
+            Pagination::Cursor => {
+                let transaction = self.0.transaction().await?;
+
+                transaction
+                    .batch_execute(&format!(
+                        "DECLARE users_page CURSOR FOR SELECT id, name, hair_color, created_at FROM users ORDER BY id;
+                        MOVE ABSOLUTE {offset} IN users_page"
+                    ))
+                    .await?;
+                let rows = transaction
+                    .query(&format!("FETCH {PAGE_SIZE} FROM users_page"), &[])
+                    .await?;
+                transaction.commit().await?;
+
+                rows
+            }
+        };
+
+        Ok(rows.iter().map(User::from_row).collect())
+    }
+
+    fn modes() -> &'static [elephantry_benchmark::Mode] {
+        &elephantry_benchmark::Mode::ALL
+    }
+
+    async fn lookup(&mut self, mode: elephantry_benchmark::Mode) -> Result<String, Self::Error> {
+        use elephantry_benchmark::Mode;
+
+        let query = "SELECT name FROM users WHERE id = $1";
+
+        match mode {
+            Mode::Simple => {
+                let query = format!(
+                    "SELECT name FROM users WHERE id = '{}'",
+                    elephantry_benchmark::UUID
+                );
+                let name = self
+                    .0
+                    .simple_query(&query)
+                    .await?
+                    .into_iter()
+                    .find_map(|x| match x {
+                        tokio_postgres::SimpleQueryMessage::Row(row) => {
+                            row.get(0).map(String::from)
+                        }
+                        _ => None,
+                    })
+                    .unwrap();
+
+                Ok(name)
+            }
+            Mode::Unprepared => self
+                .0
+                .query_typed_one(
+                    query,
+                    &[(
+                        &elephantry_benchmark::UUID,
+                        tokio_postgres::types::Type::UUID,
+                    )],
+                )
+                .await
+                .map(|x| x.get(0)),
+            Mode::Prepared => {
+                let statement = match &self.1 {
+                    Some(statement) => statement.clone(),
+                    None => self.1.insert(self.0.prepare(query).await?).clone(),
+                };
+
+                self.0
+                    .query_one(&statement, &[&elephantry_benchmark::UUID])
+                    .await
+                    .map(|x| x.get(0))
+            }
+        }
+    }
+
+    async fn stream(&mut self) -> Result<usize, Self::Error> {
+        use futures_util::TryStreamExt;
+
+        self.0
+            .query_raw("SELECT name FROM users", std::iter::empty::<i32>())
+            .await?
+            .try_fold(0, |len, row| async move {
+                Ok(len + row.get::<_, &str>(0).len())
+            })
+            .await
+    }
+
+    async fn fetch_types(&mut self) -> Result<Vec<Self::Types>, Self::Error> {
+        let results = self
+            .0
+            .query("SELECT * FROM types", &[])
+            .await?
+            .iter()
+            .map(Types::from_row)
+            .collect();
+
+        Ok(results)
+    }
+
+    async fn insert_types(&mut self) -> Result<(), Self::Error> {
+        let types = Types::new();
+
+        self.0
+            .execute(
+                "INSERT INTO types (document, amount, data, integers, floats, created_at, duration, address, mood, period)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                &[
+                    &types.document,
+                    &types.amount,
+                    &types.data,
+                    &types.integers,
+                    &types.floats,
+                    &types.created_at,
+                    &types.duration,
+                    &types.address,
+                    &types.mood,
+                    &types.period,
+                ],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn insert_bytea(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0
+            .execute("INSERT INTO payloads (data) VALUES ($1)", &[&data])
+            .await
+            .map(|_| ())
+    }
+
+    async fn fetch_bytea(&mut self) -> Result<usize, Self::Error> {
+        let data: Vec<u8> = self
+            .0
+            .query_one("SELECT data FROM payloads WHERE id = 1", &[])
+            .await?
+            .get(0);
+
+        Ok(data.len())
+    }
+
+    async fn insert_text(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.0
+            .execute("INSERT INTO payloads (content) VALUES ($1)", &[&content])
+            .await
+            .map(|_| ())
+    }
+
+    async fn fetch_text(&mut self) -> Result<usize, Self::Error> {
+        let content: String = self
+            .0
+            .query_one("SELECT content FROM payloads WHERE id = 1", &[])
+            .await?
+            .get(0);
+
+        Ok(content.len())
+    }
+
+    fn insert_many_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const { &[Implementation::LOOP, Implementation::optimized("copy")] }
+    }
+
+    async fn insert_many(&mut self, implementation: &str, n: usize) -> Result<(), Self::Error> {
+        match implementation {
+            "loop" => self.insert_users(n).await,
+            "copy" => {
+                self.copy_in(elephantry_benchmark::CopyFormat::Binary, n)
+                    .await
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn pipeline_implementations() -> &'static [elephantry_benchmark::Implementation] {
+        use elephantry_benchmark::Implementation;
+
+        const {
+            &[
+                Implementation::SERIAL,
+                Implementation::optimized("pipelined"),
+            ]
+        }
+    }
+
+    /**
+     * The client pipelines the requests of concurrent futures: every query
+     * is sent before the first reply is awaited.
+     */
+    async fn pipeline(&mut self, implementation: &str) -> Result<usize, Self::Error> {
+        use tokio_postgres::types::Type;
+
+        if implementation == "serial" {
+            return self.serial().await;
+        }
+
+        let client = &self.0;
+
+        let inserts = (0..elephantry_benchmark::PIPELINE_SIZE).map(|_| {
+            client.query_typed(
+                "INSERT INTO users (name, hair_color) VALUES ($1, $2)",
+                &[(&"User", Type::VARCHAR), (&"hair color", Type::VARCHAR)],
+            )
+        });
+        let lookups = (0..elephantry_benchmark::PIPELINE_SIZE).map(|_| {
+            client.query_typed_one(
+                "SELECT name FROM users WHERE id = $1",
+                &[(&elephantry_benchmark::UUID, Type::UUID)],
+            )
+        });
+
+        let (_, names) = futures_util::future::try_join(
+            futures_util::future::try_join_all(inserts),
+            futures_util::future::try_join_all(lookups),
+        )
+        .await?;
+
+        Ok(names.iter().map(|x| x.get::<_, &str>(0).len()).sum())
+    }
+
+    fn copy_in_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    async fn copy_in(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+        n: usize,
+    ) -> Result<(), Self::Error> {
+        use futures_util::SinkExt;
+        use tokio_postgres::types::Type;
+
+        let sink = self
+            .0
+            .copy_in::<_, bytes::Bytes>(&format.copy_in_statement())
+            .await?;
+
+        if format == elephantry_benchmark::CopyFormat::Binary {
+            let writer = tokio_postgres::binary_copy::BinaryCopyInWriter::new(
+                sink,
+                &[Type::VARCHAR, Type::VARCHAR],
+            );
+            futures_util::pin_mut!(writer);
+
+            for _ in 0..n {
+                writer.as_mut().write(&[&"User", &"hair color"]).await?;
+            }
+
+            writer.finish().await.map(|_| ())
+        } else {
+            futures_util::pin_mut!(sink);
+
+            sink.send(format.users(n).into()).await?;
+            sink.finish().await.map(|_| ())
+        }
+    }
+
+    fn copy_out_formats() -> &'static [elephantry_benchmark::CopyFormat] {
+        &elephantry_benchmark::CopyFormat::ALL
+    }
+
+    async fn copy_out(
+        &mut self,
+        format: elephantry_benchmark::CopyFormat,
+    ) -> Result<usize, Self::Error> {
+        use futures_util::TryStreamExt;
+
+        self.0
+            .copy_out(&format.copy_out_statement())
+            .await?
+            .try_fold(0, |len, chunk| async move { Ok(len + chunk.len()) })
+            .await
+    }
+
+    async fn load(
+        &mut self,
+        users: &[elephantry_benchmark::dataset::User],
+        posts: &[elephantry_benchmark::dataset::Post],
+    ) -> Result<(), Self::Error> {
+        use elephantry_benchmark::dataset;
+        use futures_util::SinkExt;
+
+        for (statement, data) in [
+            (dataset::USERS_COPY, dataset::users_text(users)),
+            (dataset::POSTS_COPY, dataset::posts_text(posts)),
+        ] {
+            let sink = self.0.copy_in::<_, bytes::Bytes>(statement).await?;
+            futures_util::pin_mut!(sink);
+
+            sink.send(data.into()).await?;
+            sink.finish().await?;
+        }
+
+        Ok(())
+    }
+}